@@ -8,31 +8,186 @@ use serde_json::{Value};
 
 use crate::ac::generic::Generic;
 use crate::ac::hare::Hare;
+use crate::ac::traits::Traits;
+use crate::clock::GameClock;
 use crate::error;
 use crate::event::ZoneEvent;
-use crate::message::{Message, ZoneMessage};
+use crate::message::{Hunger, Message, Thirst, ZoneMessage};
+use crate::occupancy::Occupant;
+use crate::registry::Zones;
+use crate::scheduler::Scheduler;
 use crate::zone::Zone;
-use crate::TICK_EACH_MS;
 
 pub mod generic;
 pub mod hare;
+pub mod pending;
+pub mod traits;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub const MAX_HUNGER: Hunger = 100;
+pub const MAX_THIRST: Thirst = 100;
+
+/// Variants are named like server animated corpse types
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Type {
     HARE,
     GOAT,
     MOORHEN,
     PIG,
     CRAB,
+    FOX,
+}
+
+/// How a species react to another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Hunts,
+    Flees,
+    Ignores,
 }
 
+/// Species relationships: (species, other species, relation of species to other one).
+/// Missing pairs are `Relation::Ignores`.
+pub const RELATIONS: &[(Type, Type, Relation)] = &[
+    (Type::FOX, Type::HARE, Relation::Hunts),
+    (Type::FOX, Type::MOORHEN, Relation::Hunts),
+    (Type::HARE, Type::FOX, Relation::Flees),
+    (Type::MOORHEN, Type::FOX, Relation::Flees),
+    (Type::GOAT, Type::FOX, Relation::Flees),
+];
+
+/// When a species is awake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Diurnal,
+    Nocturnal,
+}
+
+/// Fear state: calm, alert (stop and watch threat), panic (flee), recovering (skittish)
+/// then calm again
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Emotion {
+    #[default]
+    Calm,
+    Alert,
+    Panic,
+    Recovering,
+}
+
+impl Emotion {
+    /// Alert and panicking animated corpses don't move on their own (panic moves are fear ones)
+    pub fn holds_still(&self) -> bool {
+        matches!(self, Emotion::Alert | Emotion::Panic)
+    }
+}
+
+impl Type {
+    pub fn glyph(&self) -> char {
+        match self {
+            Type::HARE => 'h',
+            Type::GOAT => 'g',
+            Type::MOORHEN => 'm',
+            Type::PIG => 'p',
+            Type::CRAB => 'c',
+            Type::FOX => 'f',
+        }
+    }
+
+    pub fn activity(&self) -> Activity {
+        match self {
+            Type::HARE => Activity::Nocturnal,
+            Type::GOAT => Activity::Diurnal,
+            Type::MOORHEN => Activity::Diurnal,
+            Type::PIG => Activity::Diurnal,
+            Type::CRAB => Activity::Nocturnal,
+            Type::FOX => Activity::Nocturnal,
+        }
+    }
+
+    pub fn moves_in_pack(&self) -> bool {
+        match self {
+            Type::HARE => true,
+            Type::GOAT => true,
+            Type::MOORHEN => true,
+            Type::PIG => true,
+            Type::CRAB => false,
+            Type::FOX => false,
+        }
+    }
+
+    /// Territorial species confront intruders of their home range instead of fleeing
+    pub fn territorial(&self) -> bool {
+        match self {
+            Type::HARE => false,
+            Type::GOAT => false,
+            Type::MOORHEN => false,
+            Type::PIG => false,
+            Type::CRAB => true,
+            Type::FOX => false,
+        }
+    }
+
+    /// Maximum distance from home where animated corpse wander (territory for crabs)
+    pub fn home_range(&self) -> u32 {
+        match self {
+            Type::HARE => 6,
+            Type::GOAT => 8,
+            Type::MOORHEN => 5,
+            Type::PIG => 6,
+            Type::CRAB => 2,
+            Type::FOX => 10,
+        }
+    }
+
+    pub fn relation_to(&self, other: &Type) -> Relation {
+        RELATIONS
+            .iter()
+            .find(|(type_, other_type, _)| type_ == self && other_type == other)
+            .map(|(_, _, relation)| *relation)
+            .unwrap_or(Relation::Ignores)
+    }
+
+    /// Hunters feed on their prey instead of foraging
+    pub fn hunts(&self) -> bool {
+        RELATIONS
+            .iter()
+            .any(|(type_, _, relation)| type_ == self && *relation == Relation::Hunts)
+    }
+
+    /// Tile sharing policy: can an animated corpse of this type stand on same tile than
+    /// given occupant
+    pub fn can_share_tile_with(&self, occupant: &Occupant) -> bool {
+        match occupant {
+            Occupant::Character(_) => false,
+            Occupant::AnimatedCorpse(_, other_type) => {
+                self == other_type && matches!(self, Type::MOORHEN | Type::CRAB)
+            }
+        }
+    }
+}
+
+/// Build animated corpse from api value. Its traits, if not given, are generated from
+/// given seed.
 pub fn animated_corpse_from_value(
     value: Value,
+    traits_seed: u64,
 ) -> Result<Box<dyn AnimatedCorpse + Send + Sync>, error::Error> {
-    let base: AnimatedCorpseBase = serde_json::from_value(value.clone())?;
+    let mut base: AnimatedCorpseBase = serde_json::from_value(value.clone())?;
+    if base.traits.is_none() {
+        base.traits = Some(Traits::generate(traits_seed, base.id));
+    }
+    if base.home.is_none() {
+        base.home = Some((base.zone_row_i, base.zone_col_i));
+    }
+    Ok(animated_corpse_from_base(base))
+}
+
+pub fn animated_corpse_from_base(
+    base: AnimatedCorpseBase,
+) -> Box<dyn AnimatedCorpse + Send + Sync> {
     match base.type_ {
-        Type::HARE => Ok(Box::new(Hare::new(base))),
-        _ => Ok(Box::new(Generic::new(base))),
+        Type::HARE => Box::new(Hare::new(base)),
+        _ => Box::new(Generic::new(base)),
     }
 }
 
@@ -44,6 +199,29 @@ pub struct AnimatedCorpseBase {
     pub world_col_i: u32,
     pub zone_row_i: u32,
     pub zone_col_i: u32,
+    /// Not known by server, start fed
+    #[serde(default)]
+    pub hunger: Hunger,
+    #[serde(default)]
+    pub thirst: Thirst,
+    /// Remaining animations to stay where it is (random moves are skipped)
+    #[serde(default)]
+    pub linger: u8,
+    #[serde(default)]
+    pub asleep: bool,
+    #[serde(default)]
+    pub traits: Option<Traits>,
+    /// Zone position around which it lives, spawn position if not given
+    #[serde(default)]
+    pub home: Option<(u32, u32)>,
+    /// Direction (row and col modifiers) of last move
+    #[serde(default)]
+    pub heading: Option<(i8, i8)>,
+    #[serde(default)]
+    pub emotion: Emotion,
+    /// Remaining fear animations before emotion eases
+    #[serde(default)]
+    pub emotion_cooldown: u8,
 }
 
 pub trait AnimatedCorpse {
@@ -79,41 +257,110 @@ pub trait AnimatedCorpse {
     fn set_zone_col_i(&mut self, zone_col_i: u32) {
         self.base_mut().zone_col_i = zone_col_i
     }
+    fn hunger(&self) -> Hunger {
+        self.base().hunger
+    }
+    fn set_hunger(&mut self, hunger: Hunger) {
+        self.base_mut().hunger = hunger
+    }
+    fn thirst(&self) -> Thirst {
+        self.base().thirst
+    }
+    fn set_thirst(&mut self, thirst: Thirst) {
+        self.base_mut().thirst = thirst
+    }
+    fn linger(&self) -> u8 {
+        self.base().linger
+    }
+    fn set_linger(&mut self, linger: u8) {
+        self.base_mut().linger = linger
+    }
+    fn home(&self) -> (u32, u32) {
+        self.base()
+            .home
+            .unwrap_or((self.zone_row_i(), self.zone_col_i()))
+    }
+    fn heading(&self) -> Option<(i8, i8)> {
+        self.base().heading
+    }
+    fn set_heading(&mut self, heading: (i8, i8)) {
+        self.base_mut().heading = Some(heading)
+    }
+    fn traits(&self) -> Traits {
+        self.base().traits.unwrap_or_default()
+    }
+    fn asleep(&self) -> bool {
+        self.base().asleep
+    }
+    fn set_asleep(&mut self, asleep: bool) {
+        self.base_mut().asleep = asleep
+    }
+    fn emotion(&self) -> Emotion {
+        self.base().emotion
+    }
+    fn emotion_cooldown(&self) -> u8 {
+        self.base().emotion_cooldown
+    }
+    fn set_emotion(&mut self, emotion: Emotion, cooldown: u8) {
+        self.base_mut().emotion = emotion;
+        self.base_mut().emotion_cooldown = cooldown;
+    }
     fn on_event(&self, event: &ZoneEvent, zone: &Zone) -> Vec<Message>;
     fn on_message(&mut self, message: ZoneMessage);
     fn animate(&self, zone: &Zone, tick_count: u64) -> Vec<Message>;
 }
 
-pub async fn animate(zones: &Mutex<Vec<Zone>>, channel_sender: &Sender<Message>) {
-    let mut tick_count: u64 = 0;
-    let mut last_tick = Instant::now();
+pub async fn animate(
+    zones: &Mutex<Zones>,
+    channel_sender: &Sender<Message>,
+    mut scheduler: Scheduler,
+    pending_move_timeout: Duration,
+    clock: GameClock,
+) {
     log::info!("Begin animation loop");
     loop {
-        let now = Instant::now();
-        let last_tick_duration = now - last_tick;
-        let sleep_for = TICK_EACH_MS - last_tick_duration.as_millis() as u64;
-        log::debug!(
-            "Last tick duration is {} ms, sleep for {} ms",
-            last_tick_duration.as_millis(),
-            sleep_for
-        );
-        sleep(Duration::from_millis(sleep_for)).await;
-        last_tick = Instant::now();
+        let sleep_for = scheduler.until_next(Instant::now());
+        log::debug!("Sleep for {} ms", sleep_for.as_millis());
+        sleep(sleep_for).await;
+
+        let tick_start = Instant::now();
+        let time = clock.now();
         let mut messages: Vec<Message> = vec![];
 
         {
-            for zone in zones.lock().await.iter_mut() {
-                messages.extend(zone.animate(tick_count))
+            let mut zones = zones.lock().await;
+            scheduler.register(&zones.coordinates());
+            let due = scheduler.due(tick_start);
+            log::debug!(
+                "Animate {} of {} zones ({} hits on not loaded zones so far)",
+                due.len(),
+                zones.len(),
+                zones.total_missing_zone_hits()
+            );
+            for (coordinates, tick_count) in due {
+                if let Some(zone) = zones.get_mut(coordinates) {
+                    if !zone.pending_moves.expire(pending_move_timeout).is_empty() {
+                        zone.refresh_occupancy();
+                    }
+                    messages.extend(zone.animate(tick_count, &time))
+                }
             }
         };
 
         for message in messages {
             if let Err(err) = channel_sender.send(message).await {
                 log::error!("Message channel is closed (from animate): {}", err);
-                break
+                break;
             };
         }
 
-        tick_count += 1;
+        let tick_duration = tick_start.elapsed();
+        if tick_duration > scheduler.tick_each() {
+            log::warn!(
+                "Animation took {} ms, more than tick interval ({} ms)",
+                tick_duration.as_millis(),
+                scheduler.tick_each().as_millis()
+            );
+        }
     }
 }
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::message::{AnimatedCorpseId, ZoneColI, ZoneRowI};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PendingMove {
+    pub to_row_i: ZoneRowI,
+    pub to_col_i: ZoneColI,
+    pub since: Instant,
+}
+
+/// Move requests sent to server and not yet acknowledged by an ANIMATED_CORPSE_MOVE event
+#[derive(Debug)]
+pub struct PendingMoves {
+    moves: HashMap<AnimatedCorpseId, PendingMove>,
+}
+
+impl PendingMoves {
+    pub fn new() -> Self {
+        Self {
+            moves: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, animated_corpse_id: AnimatedCorpseId) -> bool {
+        self.moves.contains_key(&animated_corpse_id)
+    }
+
+    /// Register a move request. Return false (and register nothing) if animated corpse
+    /// already have a pending move.
+    pub fn register(
+        &mut self,
+        animated_corpse_id: AnimatedCorpseId,
+        to_row_i: ZoneRowI,
+        to_col_i: ZoneColI,
+    ) -> bool {
+        if self.contains(animated_corpse_id) {
+            return false;
+        }
+
+        self.moves.insert(
+            animated_corpse_id,
+            PendingMove {
+                to_row_i,
+                to_col_i,
+                since: Instant::now(),
+            },
+        );
+        true
+    }
+
+    pub fn acknowledge(
+        &mut self,
+        animated_corpse_id: AnimatedCorpseId,
+        row_i: ZoneRowI,
+        col_i: ZoneColI,
+    ) -> Option<PendingMove> {
+        let pending_move = self.moves.remove(&animated_corpse_id)?;
+        if (pending_move.to_row_i, pending_move.to_col_i) != (row_i, col_i) {
+            log::debug!(
+                "Animated corpse {} moved to {}.{} but {}.{} was requested",
+                animated_corpse_id,
+                row_i,
+                col_i,
+                pending_move.to_row_i,
+                pending_move.to_col_i
+            );
+        }
+        Some(pending_move)
+    }
+
+    /// Forget pending move of animated corpse which left zone
+    pub fn forget(&mut self, animated_corpse_id: AnimatedCorpseId) -> Option<PendingMove> {
+        self.moves.remove(&animated_corpse_id)
+    }
+
+    /// Forget pending moves older than timeout (their acknowledgement is considered lost)
+    pub fn expire(&mut self, timeout: Duration) -> Vec<(AnimatedCorpseId, PendingMove)> {
+        let expired: Vec<AnimatedCorpseId> = self
+            .moves
+            .iter()
+            .filter(|(_, pending_move)| pending_move.since.elapsed() > timeout)
+            .map(|(animated_corpse_id, _)| *animated_corpse_id)
+            .collect();
+
+        let mut expired_moves = vec![];
+        for animated_corpse_id in expired {
+            if let Some(pending_move) = self.moves.remove(&animated_corpse_id) {
+                log::warn!(
+                    "Move of animated corpse {} to {}.{} not acknowledged after {} ms",
+                    animated_corpse_id,
+                    pending_move.to_row_i,
+                    pending_move.to_col_i,
+                    pending_move.since.elapsed().as_millis()
+                );
+                expired_moves.push((animated_corpse_id, pending_move));
+            }
+        }
+
+        expired_moves
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&AnimatedCorpseId, &PendingMove)> {
+        self.moves.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_refuses_second_move() {
+        let mut pending_moves = PendingMoves::new();
+
+        assert!(pending_moves.register(1, 2, 3));
+        assert!(!pending_moves.register(1, 4, 5));
+        assert!(pending_moves.contains(1));
+        assert!(!pending_moves.contains(2));
+    }
+
+    #[test]
+    fn acknowledge_forget_move() {
+        let mut pending_moves = PendingMoves::new();
+        pending_moves.register(1, 2, 3);
+
+        let pending_move = pending_moves.acknowledge(1, 2, 3).unwrap();
+        assert_eq!((pending_move.to_row_i, pending_move.to_col_i), (2, 3));
+        assert!(!pending_moves.contains(1));
+        assert!(pending_moves.acknowledge(1, 2, 3).is_none());
+    }
+
+    #[test]
+    fn expire_old_moves_only() {
+        let mut pending_moves = PendingMoves::new();
+        pending_moves.register(1, 2, 3);
+
+        assert!(pending_moves.expire(Duration::from_secs(60)).is_empty());
+        std::thread::sleep(Duration::from_millis(2));
+        let expired = pending_moves.expire(Duration::from_millis(1));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, 1);
+        assert!(!pending_moves.contains(1));
+    }
+}
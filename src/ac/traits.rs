@@ -0,0 +1,100 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_derive::{Deserialize, Serialize};
+
+/// Traits from which an animated corpse is considered bold, curious, etc.
+pub const HIGH_TRAIT: f32 = 0.75;
+/// Traits are not stored: keeping the same seed is what keep same traits across restarts
+pub const DEFAULT_TRAITS_SEED: u64 = 0x726F_6C6C_6163;
+
+/// Personality of an animated corpse, each trait from 0.0 to 1.0 (0.5 is average)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Traits {
+    /// Bold ones let threats come closer before fleeing
+    pub boldness: f32,
+    /// Curious ones wander more often
+    pub curiosity: f32,
+    /// Sociable ones stay closer to their pack
+    pub sociability: f32,
+    /// Fast ones are animated more often
+    pub speed: f32,
+}
+
+impl Default for Traits {
+    fn default() -> Self {
+        Self {
+            boldness: 0.5,
+            curiosity: 0.5,
+            sociability: 0.5,
+            speed: 0.5,
+        }
+    }
+}
+
+impl Traits {
+    /// Random traits of given animated corpse. Same seed always give same traits to same
+    /// animated corpse.
+    pub fn generate(seed: u64, animated_corpse_id: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(
+            seed ^ (animated_corpse_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+        );
+        Self {
+            boldness: rng.gen(),
+            curiosity: rng.gen(),
+            sociability: rng.gen(),
+            speed: rng.gen(),
+        }
+    }
+
+    /// Scale an animation interval (like `Behavior::animate_each`) according to speed:
+    /// from 1.5 times (slowest) to 0.5 time (fastest) given interval
+    pub fn scale_animate_each(&self, animate_each: u8) -> u8 {
+        (animate_each as f32 * (1.5 - self.speed)).round().max(1.0) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_is_stable() {
+        assert_eq!(
+            Traits::generate(DEFAULT_TRAITS_SEED, 1),
+            Traits::generate(DEFAULT_TRAITS_SEED, 1)
+        );
+        assert_ne!(
+            Traits::generate(DEFAULT_TRAITS_SEED, 1),
+            Traits::generate(DEFAULT_TRAITS_SEED, 2)
+        );
+        assert_ne!(Traits::generate(1, 1), Traits::generate(2, 1));
+    }
+
+    #[test]
+    fn generate_traits_in_range() {
+        for animated_corpse_id in 0..100 {
+            let traits = Traits::generate(DEFAULT_TRAITS_SEED, animated_corpse_id);
+            for trait_ in [
+                traits.boldness,
+                traits.curiosity,
+                traits.sociability,
+                traits.speed,
+            ]
+            .iter()
+            {
+                assert!((0.0..1.0).contains(trait_));
+            }
+        }
+    }
+
+    #[test]
+    fn scale_animate_each_by_speed() {
+        let mut traits = Traits::default();
+        assert_eq!(traits.scale_animate_each(4), 4);
+        traits.speed = 0.0;
+        assert_eq!(traits.scale_animate_each(4), 6);
+        traits.speed = 1.0;
+        assert_eq!(traits.scale_animate_each(4), 2);
+        assert_eq!(traits.scale_animate_each(1), 1);
+    }
+}
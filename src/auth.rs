@@ -0,0 +1,153 @@
+use std::fmt;
+
+pub const HEADER_NAME__DISABLE_AUTH_TOKEN: &str = "DISABLE_AUTH_TOKEN";
+pub const HEADER_NAME__AUTHORIZATION: &str = "Authorization";
+/// Printed instead of secrets (tokens and passwords), like in logs
+pub const REDACTED: &str = "<redacted>";
+
+/// Redacted secret, for Debug implementations
+pub fn redacted(secret: &Option<String>) -> Option<&'static str> {
+    secret.as_ref().map(|_| REDACTED)
+}
+
+/// Service account credentials
+#[derive(Clone)]
+pub enum Credentials {
+    Token(String),
+    Basic { username: String, password: String },
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::Token(_) => f.debug_tuple("Token").field(&REDACTED).finish(),
+            Credentials::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &REDACTED)
+                .finish(),
+        }
+    }
+}
+
+/// Authentication applied to every api request and to websocket handshake
+#[derive(Clone, Default)]
+pub struct Auth {
+    pub disable_auth_token: Option<String>,
+    pub credentials: Option<Credentials>,
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth")
+            .field("disable_auth_token", &redacted(&self.disable_auth_token))
+            .field("credentials", &self.credentials)
+            .finish()
+    }
+}
+
+impl Auth {
+    pub fn new(disable_auth_token: Option<String>, credentials: Option<Credentials>) -> Self {
+        Self {
+            disable_auth_token,
+            credentials,
+        }
+    }
+
+    /// Http headers (name, value) to add to requests
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![];
+
+        if let Some(disable_auth_token) = &self.disable_auth_token {
+            headers.push((HEADER_NAME__DISABLE_AUTH_TOKEN, disable_auth_token.clone()));
+        }
+
+        match &self.credentials {
+            Some(Credentials::Token(token)) => {
+                headers.push((HEADER_NAME__AUTHORIZATION, format!("Bearer {}", token)))
+            }
+            Some(Credentials::Basic { username, password }) => headers.push((
+                HEADER_NAME__AUTHORIZATION,
+                format!(
+                    "Basic {}",
+                    base64::encode(format!("{}:{}", username, password))
+                ),
+            )),
+            None => {}
+        }
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_without_credentials() {
+        assert!(Auth::default().headers().is_empty());
+    }
+
+    #[test]
+    fn headers_with_disable_auth_token() {
+        let auth = Auth::new(Some("disable".to_string()), None);
+
+        assert_eq!(
+            auth.headers(),
+            vec![(HEADER_NAME__DISABLE_AUTH_TOKEN, "disable".to_string())]
+        );
+    }
+
+    #[test]
+    fn headers_with_token() {
+        let auth = Auth::new(None, Some(Credentials::Token("abc".to_string())));
+
+        assert_eq!(
+            auth.headers(),
+            vec![(HEADER_NAME__AUTHORIZATION, "Bearer abc".to_string())]
+        );
+    }
+
+    #[test]
+    fn headers_with_basic_credentials() {
+        let auth = Auth::new(
+            Some("disable".to_string()),
+            Some(Credentials::Basic {
+                username: "rollac".to_string(),
+                password: "secret".to_string(),
+            }),
+        );
+
+        assert_eq!(
+            auth.headers(),
+            vec![
+                (HEADER_NAME__DISABLE_AUTH_TOKEN, "disable".to_string()),
+                // base64 of "rollac:secret"
+                (
+                    HEADER_NAME__AUTHORIZATION,
+                    "Basic cm9sbGFjOnNlY3JldA==".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_redact_secrets() {
+        for credentials in [
+            Credentials::Token("secret".to_string()),
+            Credentials::Basic {
+                username: "rollac".to_string(),
+                password: "secret".to_string(),
+            },
+        ]
+        .iter()
+        {
+            let auth = Auth::new(Some("secret".to_string()), Some(credentials.clone()));
+            let debug = format!("{:?}", auth);
+
+            assert!(!debug.contains("secret"));
+            assert!(debug.contains(REDACTED));
+        }
+    }
+}
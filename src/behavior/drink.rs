@@ -0,0 +1,231 @@
+use crate::ac::{AnimatedCorpse, Type, MAX_THIRST};
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::ZoneEvent;
+use crate::message::{Message, SendEventMessage, Thirst, ZoneMessage};
+use crate::tile::zone::TileCategory;
+use crate::util;
+use crate::zone::Zone;
+
+/// Thirst grows at each animation; when thirsty, go to nearest water tile, drink and
+/// linger near it for a while
+pub struct Drink {
+    pub animate_each: u8,
+    /// Thirst added at each animation
+    pub thirst_rate: Thirst,
+    /// Thirst from which animated corpse look for water
+    pub thirsty_at: Thirst,
+    /// How far (in tiles) water can be seen
+    pub sight: u32,
+    /// Animations count to stay near water after drinking
+    pub linger: u8,
+}
+
+impl Drink {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        let (animate_each, thirst_rate) = match animated_corpse.type_() {
+            Type::HARE => (3, 5),
+            Type::GOAT => (5, 4),
+            Type::MOORHEN => (2, 2),
+            Type::PIG => (3, 5),
+            Type::CRAB => (7, 1),
+            Type::FOX => (4, 4),
+        };
+        let (sight, linger) = match animated_corpse.type_() {
+            Type::HARE => (8, 2),
+            Type::GOAT => (8, 3),
+            Type::MOORHEN => (6, 6),
+            Type::PIG => (6, 4),
+            Type::CRAB => (3, 2),
+            Type::FOX => (8, 2),
+        };
+        Self {
+            animate_each,
+            thirst_rate,
+            thirsty_at: MAX_THIRST / 2,
+            sight,
+            linger,
+        }
+    }
+}
+
+impl Behavior for Drink {
+    fn name(&self) -> &str {
+        "drink"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        Some(self.animate_each)
+    }
+
+    fn on_event(
+        &self,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _event: &ZoneEvent,
+        _zone: &Zone,
+    ) -> Vec<Message> {
+        vec![]
+    }
+
+    fn on_animate(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        let mut messages: Vec<Message> = vec![];
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        let zone_coordinates = (animated_corpse.world_row_i(), animated_corpse.world_col_i());
+
+        let mut linger = animated_corpse.linger().saturating_sub(1);
+        let mut thirst = animated_corpse
+            .thirst()
+            .saturating_add(self.thirst_rate)
+            .min(MAX_THIRST);
+
+        // Asleep animated corpse don't look for water (but still get thirsty)
+        if thirst >= self.thirsty_at && !animated_corpse.asleep() {
+            let waters = zone.tiles_near(TileCategory::Water, position, self.sight);
+            match waters.first() {
+                // Water tiles are generally not traversable, drink from around
+                Some(water_position) if util::is_near(position, *water_position, 1) => {
+                    thirst = 0;
+                    linger = self.linger;
+                }
+                // Alert or panicking ones don't walk to it
+                Some(_) if animated_corpse.emotion().holds_still() => {}
+                Some(water_position) => {
+                    if let Some((move_to_row_i, move_to_col_i)) =
+                        zone.step_toward(animated_corpse.as_ref(), *water_position)
+                    {
+                        messages.push(Message::Event(
+                            SendEventMessage::RequireAnimatedCorpseMove(
+                                animated_corpse.id(),
+                                move_to_row_i,
+                                move_to_col_i,
+                            ),
+                            zone_coordinates,
+                        ));
+                    }
+                }
+                // No water in sight, let random moves find some
+                None => {}
+            }
+        }
+
+        if linger != animated_corpse.linger() {
+            messages.push(Message::Zone(
+                ZoneMessage::UpdateAnimatedCorpseLinger(animated_corpse.id(), linger),
+                zone_coordinates,
+            ));
+        }
+        if thirst != animated_corpse.thirst() {
+            messages.push(Message::Zone(
+                ZoneMessage::UpdateAnimatedCorpseThirst(animated_corpse.id(), thirst),
+                zone_coordinates,
+            ));
+        }
+
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Emotion;
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const TIME: GameTime = GameTime { minute_of_day: 0 };
+
+    fn goat(thirst: Thirst, position: (u32, u32)) -> Box<dyn AnimatedCorpse + Send + Sync> {
+        let mut goat = animated_corpse(1, Type::GOAT, position);
+        goat.set_thirst(thirst);
+        goat
+    }
+
+    fn animate(lines: &str, goat: Box<dyn AnimatedCorpse + Send + Sync>) -> Vec<Message> {
+        let zone = zone(lines, vec![goat]);
+        let drink = Drink::from_animated_corpse(zone.animated_corpses[0].as_ref());
+        drink.on_animate(&zone.animated_corpses[0], &zone, &TIME)
+    }
+
+    fn thirsts(messages: &[Message]) -> Vec<Thirst> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(ZoneMessage::UpdateAnimatedCorpseThirst(_, thirst), _) => {
+                    Some(*thirst)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn lingers(messages: &[Message]) -> Vec<u8> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(ZoneMessage::UpdateAnimatedCorpseLinger(_, linger), _) => {
+                    Some(*linger)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn thirst_grows_and_is_capped() {
+        assert_eq!(thirsts(&animate("...\n", goat(0, (0, 1)))), vec![4]);
+        assert_eq!(
+            thirsts(&animate("...\n", goat(MAX_THIRST - 1, (0, 1)))),
+            vec![MAX_THIRST]
+        );
+        assert!(animate("...\n", goat(MAX_THIRST, (0, 1))).is_empty());
+    }
+
+    #[test]
+    fn thirsty_one_steps_toward_nearest_water() {
+        let messages = animate("~....~..\n", goat(60, (0, 3)));
+
+        assert_eq!(moves(&messages), vec![(0, 4)]);
+        assert_eq!(thirsts(&messages), vec![64]);
+        // Not thirsty yet
+        assert!(moves(&animate("~....~..\n", goat(0, (0, 3)))).is_empty());
+    }
+
+    #[test]
+    fn drinking_reset_thirst_and_set_linger() {
+        let mut lingering = goat(60, (0, 1));
+        lingering.set_linger(1);
+        let messages = animate("~..\n", lingering);
+
+        assert_eq!(thirsts(&messages), vec![0]);
+        assert_eq!(lingers(&messages), vec![3]);
+        assert!(moves(&messages).is_empty());
+    }
+
+    #[test]
+    fn linger_decrease() {
+        let mut lingering = goat(0, (0, 1));
+        lingering.set_linger(2);
+
+        assert_eq!(lingers(&animate("~..\n", lingering)), vec![1]);
+        assert!(lingers(&animate("~..\n", goat(0, (0, 1)))).is_empty());
+    }
+
+    #[test]
+    fn asleep_or_holding_still_ones_stay() {
+        let mut asleep = goat(60, (0, 3));
+        asleep.set_asleep(true);
+        let messages = animate("~....~..\n", asleep);
+        assert!(moves(&messages).is_empty());
+        assert_eq!(thirsts(&messages), vec![64]);
+
+        for emotion in [Emotion::Alert, Emotion::Panic] {
+            let mut still = goat(60, (0, 3));
+            still.set_emotion(emotion, 1);
+            assert!(moves(&animate("~....~..\n", still)).is_empty());
+        }
+    }
+}
@@ -1,23 +1,211 @@
-use crate::ac::AnimatedCorpse;
+use crate::ac::traits::HIGH_TRAIT;
+use crate::ac::{AnimatedCorpse, Emotion, Relation, Type};
 use crate::behavior::Behavior;
+use crate::clock::GameTime;
 use crate::event::{ZoneEvent, ZoneEventType};
-use crate::message::{Message, SendEventMessage};
+use crate::message::{Alarm, Message, SendEventMessage, ZoneMessage};
 use crate::util;
 use crate::zone::Zone;
 use rand::seq::SliceRandom;
+use rand::Rng;
 use std::cmp::max;
 
-pub struct Fear {}
+/// Herd mates further than this distance don't hear alarms
+pub const ALARM_RADIUS: u32 = 5;
+/// Alarm strength loss at each propagation (from an alarmed animated corpse to its mates)
+pub const ALARM_DECAY: f32 = 0.6;
+/// Alarms weaker than this are not propagated anymore
+pub const MIN_ALARM_STRENGTH: f32 = 0.2;
+/// Threats up to this distance beyond flee distance make animated corpse alert
+pub const ALERT_MARGIN: u32 = 2;
+
+/// Chance of animated corpse to hear given alarm (of a herd mate): decaying over distance,
+/// asleep and bold ones are less receptive
+pub fn alarm_chance(animated_corpse: &(dyn AnimatedCorpse + Send + Sync), alarm: &Alarm) -> f64 {
+    let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+    let distance = util::distance(position, alarm.position);
+    if alarm.animated_corpse_id == animated_corpse.id()
+        || alarm.type_ != animated_corpse.type_()
+        || distance > ALARM_RADIUS
+    {
+        return 0.0;
+    }
+
+    let mut chance = alarm.strength * (1.0 - distance as f32 / (ALARM_RADIUS + 1) as f32);
+    chance *= 1.0 - animated_corpse.traits().boldness / 2.0;
+    if animated_corpse.asleep() {
+        chance /= 2.0;
+    }
+    chance.clamp(0.0, 1.0) as f64
+}
+
+/// Animated corpse get alert (stop and watch) when a threat is around, panic (flee) when
+/// it comes close or approaches fast, then recover before being calm again
+pub struct Fear {
+    /// Fear animations for emotion cooldown
+    pub animate_each: u8,
+    /// Cooldowns (in fear animations) of alert, panic and recovering emotions
+    pub alert_cooldown: u8,
+    pub panic_cooldown: u8,
+    pub recovering_cooldown: u8,
+}
 
 impl Fear {
-    pub fn from_animated_corpse(_animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>) -> Self {
-        Self {}
+    pub fn from_animated_corpse(animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>) -> Self {
+        let (alert_cooldown, panic_cooldown, recovering_cooldown) = match animated_corpse.type_() {
+            Type::HARE => (3, 3, 6),
+            Type::GOAT => (2, 2, 3),
+            Type::MOORHEN => (3, 2, 4),
+            Type::PIG => (2, 2, 3),
+            Type::CRAB => (2, 1, 2),
+            Type::FOX => (2, 1, 2),
+        };
+        Self {
+            animate_each: 2,
+            alert_cooldown,
+            panic_cooldown,
+            recovering_cooldown,
+        }
+    }
+
+    fn emotion_message(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        emotion: Emotion,
+        cooldown: u8,
+    ) -> Message {
+        Message::Zone(
+            ZoneMessage::UpdateAnimatedCorpseEmotion(animated_corpse.id(), emotion, cooldown),
+            (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
+        )
+    }
+
+    /// Update emotion (and flee if panicking) according to threat (character or predator)
+    /// moved to given position, from given previous position if known
+    fn on_threat(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        zone: &Zone,
+        threat: (u32, u32),
+        threat_from: Option<(u32, u32)>,
+    ) -> Vec<Message> {
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+
+        // Asleep or bold animated corpse only react to threats next to it
+        let react_distance =
+            if animated_corpse.asleep() || animated_corpse.traits().boldness >= HIGH_TRAIT {
+                1
+            } else {
+                2
+            };
+        // Asleep animated corpse don't watch around
+        let alert_distance = if animated_corpse.asleep() {
+            react_distance
+        } else {
+            react_distance + ALERT_MARGIN
+        };
+        let distance = util::distance(position, threat);
+        if distance > alert_distance {
+            return vec![];
+        }
+        // Approaching more than one tile at once is a fast movement
+        let fast = threat_from
+            .map(|from| util::distance(position, from).saturating_sub(distance) > 1)
+            .unwrap_or(false);
+
+        match animated_corpse.emotion() {
+            // Panicking and recovering ones flee as soon as threat is around
+            Emotion::Panic | Emotion::Recovering => self.escape(animated_corpse, zone, threat, 1.0),
+            Emotion::Calm | Emotion::Alert if distance <= react_distance || fast => {
+                self.escape(animated_corpse, zone, threat, 1.0)
+            }
+            // Stop and watch threat (again)
+            Emotion::Calm | Emotion::Alert => {
+                vec![self.emotion_message(animated_corpse, Emotion::Alert, self.alert_cooldown)]
+            }
+        }
+    }
+
+    /// Move away from threat at given position and alarm herd mates with given strength
+    fn escape(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        zone: &Zone,
+        threat: (u32, u32),
+        alarm_strength: f32,
+    ) -> Vec<Message> {
+        let mut messages: Vec<Message> = vec![];
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        let zone_coordinates = (animated_corpse.world_row_i(), animated_corpse.world_col_i());
+
+        let opposite_modifier = match util::position_direction_from(position, threat) {
+            Some(direction) => util::direction_modifier(util::opposite_direction(direction)),
+            // Threat is further (alarm raised by a herd mate), go straight away from it
+            None => (
+                (position.0 as i32 - threat.0 as i32).signum() as i8,
+                (position.1 as i32 - threat.1 as i32).signum() as i8,
+            ),
+        };
+        let possible_moves: Vec<(u32, u32)> = zone
+            .get_free_successors(animated_corpse)
+            .iter()
+            .map(|((to_row_i, to_col_i), _weight)| (*to_row_i, *to_col_i))
+            .collect();
+        let escape_to_row_i = max(
+            0,
+            animated_corpse.zone_row_i() as i32 + opposite_modifier.0 as i32,
+        ) as u32;
+        let escape_to_col_i = max(
+            0,
+            animated_corpse.zone_col_i() as i32 + opposite_modifier.1 as i32,
+        ) as u32;
+        let escape_to = if possible_moves.contains(&(escape_to_row_i, escape_to_col_i)) {
+            (escape_to_row_i, escape_to_col_i)
+        } else {
+            *possible_moves
+                .choose(&mut rand::thread_rng())
+                .unwrap_or(&position)
+        };
+
+        if animated_corpse.asleep() {
+            messages.push(Message::Zone(
+                ZoneMessage::UpdateAnimatedCorpseAsleep(animated_corpse.id(), false),
+                zone_coordinates,
+            ));
+        }
+        messages.push(Message::Event(
+            SendEventMessage::RequireAnimatedCorpseMove(
+                animated_corpse.id(),
+                escape_to.0,
+                escape_to.1,
+            ),
+            zone_coordinates,
+        ));
+        messages.push(self.emotion_message(animated_corpse, Emotion::Panic, self.panic_cooldown));
+        if alarm_strength >= MIN_ALARM_STRENGTH {
+            messages.push(Message::Zone(
+                ZoneMessage::Alarm(Alarm {
+                    animated_corpse_id: animated_corpse.id(),
+                    type_: animated_corpse.type_(),
+                    position,
+                    threat,
+                    strength: alarm_strength,
+                }),
+                zone_coordinates,
+            ));
+        }
+
+        messages
     }
 }
 
 impl Behavior for Fear {
+    fn name(&self) -> &str {
+        "fear"
+    }
+
     fn animate_each(&self) -> Option<u8> {
-        None
+        Some(self.animate_each)
     }
 
     fn on_event(
@@ -26,64 +214,251 @@ impl Behavior for Fear {
         event: &ZoneEvent,
         zone: &Zone,
     ) -> Vec<Message> {
-        let mut messages: Vec<Message> = vec![];
-
         match &event.event_type {
             ZoneEventType::PlayerMove {
                 to_row_i,
                 to_col_i,
-                character_id: _,
+                character_id,
             } => {
-                if let Some(direction) = util::position_direction_from(
-                    (animated_corpse.zone_row_i(), animated_corpse.zone_col_i()),
-                    (*to_row_i, *to_col_i),
-                ) {
-                    let opposite_direction = util::opposite_direction(direction);
-                    let opposite_modifier = util::direction_modifier(opposite_direction);
-                    let possible_moves: Vec<(u32, u32)> = zone
-                        .get_successors(animated_corpse.zone_row_i(), animated_corpse.zone_col_i())
-                        .iter()
-                        .map(|((to_row_i, to_col_i), _weight)| (*to_row_i, *to_col_i))
-                        .collect();
-                    let escape_to_row_i = max(
-                        0,
-                        animated_corpse.zone_row_i() as i32 + opposite_modifier.0 as i32,
-                    ) as u32;
-                    let escape_to_col_i = max(
-                        0,
-                        animated_corpse.zone_col_i() as i32 + opposite_modifier.1 as i32,
-                    ) as u32;
-                    let escape_to = if possible_moves.contains(&(escape_to_row_i, escape_to_col_i))
-                    {
-                        (escape_to_row_i, escape_to_col_i)
-                    } else {
-                        *possible_moves.choose(&mut rand::thread_rng()).unwrap_or(&(
-                            animated_corpse.zone_row_i(),
-                            animated_corpse.zone_col_i(),
-                        ))
-                    };
-
-                    messages.push(Message::Event(
-                        SendEventMessage::RequireAnimatedCorpseMove(
-                            animated_corpse.id(),
-                            escape_to.0,
-                            escape_to.1,
-                        ),
-                        (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
-                    ));
-                }
+                // Zone is not updated yet and still know previous character position
+                let from = zone
+                    .characters
+                    .iter()
+                    .find(|character| &character.id == character_id)
+                    .map(|character| (character.zone_row_i, character.zone_col_i));
+                self.on_threat(animated_corpse.as_ref(), zone, (*to_row_i, *to_col_i), from)
             }
-            _ => {}
+            ZoneEventType::AnimatedCorpseMove {
+                to_row_i,
+                to_col_i,
+                animated_corpse_id,
+            } => match zone.animated_corpse(*animated_corpse_id) {
+                Some(other)
+                    if animated_corpse.type_().relation_to(&other.type_()) == Relation::Flees =>
+                {
+                    self.on_threat(
+                        animated_corpse.as_ref(),
+                        zone,
+                        (*to_row_i, *to_col_i),
+                        Some((other.zone_row_i(), other.zone_col_i())),
+                    )
+                }
+                _ => vec![],
+            },
+            _ => vec![],
         }
-
-        messages
     }
 
     fn on_animate(
         &self,
-        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
         _zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        let cooldown = animated_corpse.emotion_cooldown();
+        let (emotion, cooldown) = match animated_corpse.emotion() {
+            Emotion::Calm => return vec![],
+            emotion if cooldown > 1 => (emotion, cooldown - 1),
+            Emotion::Panic => (Emotion::Recovering, self.recovering_cooldown),
+            Emotion::Alert | Emotion::Recovering => (Emotion::Calm, 0),
+        };
+
+        vec![self.emotion_message(animated_corpse.as_ref(), emotion, cooldown)]
+    }
+
+    fn on_alarm(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        alarm: &Alarm,
+        zone: &Zone,
     ) -> Vec<Message> {
-        vec![]
+        if !rand::thread_rng().gen_bool(alarm_chance(animated_corpse, alarm)) {
+            return vec![];
+        }
+
+        self.escape(
+            animated_corpse,
+            zone,
+            alarm.threat,
+            alarm.strength * ALARM_DECAY,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zone::tests::{animated_corpse, zone};
+
+    const LINES: &str = "..........\n..........\n..........\n";
+
+    fn alarm(position: (u32, u32), strength: f32) -> Alarm {
+        Alarm {
+            animated_corpse_id: 1,
+            type_: Type::GOAT,
+            position,
+            threat: (0, 0),
+            strength,
+        }
+    }
+
+    fn alarms(messages: &[Message]) -> Vec<f32> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(ZoneMessage::Alarm(alarm), _) => Some(alarm.strength),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn alarm_chance_decay_over_distance() {
+        let mate = animated_corpse(2, Type::GOAT, (1, 1));
+
+        let near = alarm_chance(mate.as_ref(), &alarm((1, 2), 1.0));
+        let far = alarm_chance(mate.as_ref(), &alarm((1, 5), 1.0));
+        assert!(near > far);
+        assert!(far > 0.0);
+        assert!(alarm_chance(mate.as_ref(), &alarm((1, 5), 0.5)) < far);
+        assert_eq!(alarm_chance(mate.as_ref(), &alarm((1, 7), 1.0)), 0.0);
+    }
+
+    #[test]
+    fn alarm_chance_only_for_mates() {
+        let raiser = animated_corpse(1, Type::GOAT, (1, 1));
+        let other_species = animated_corpse(2, Type::PIG, (1, 1));
+
+        assert_eq!(alarm_chance(raiser.as_ref(), &alarm((1, 1), 1.0)), 0.0);
+        assert_eq!(
+            alarm_chance(other_species.as_ref(), &alarm((1, 2), 1.0)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn alarm_chance_lower_for_asleep_ones() {
+        let awake = animated_corpse(2, Type::GOAT, (1, 1));
+        let mut asleep = animated_corpse(3, Type::GOAT, (1, 1));
+        asleep.set_asleep(true);
+
+        let awake_chance = alarm_chance(awake.as_ref(), &alarm((1, 2), 1.0));
+        let asleep_chance = alarm_chance(asleep.as_ref(), &alarm((1, 2), 1.0));
+        assert!((asleep_chance - awake_chance / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn escape_propagate_weakened_alarm() {
+        let zone = zone(LINES, vec![animated_corpse(2, Type::GOAT, (1, 1))]);
+        let fear = Fear::from_animated_corpse(&zone.animated_corpses[0]);
+        let mate = zone.animated_corpses[0].as_ref();
+
+        let messages = fear.escape(mate, &zone, (1, 0), ALARM_DECAY);
+        assert_eq!(alarms(&messages), vec![ALARM_DECAY]);
+        // Too weak to be propagated again
+        let messages = fear.escape(mate, &zone, (1, 0), MIN_ALARM_STRENGTH / 2.0);
+        assert!(alarms(&messages).is_empty());
+    }
+
+    fn emotions(messages: &[Message]) -> Vec<(Emotion, u8)> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(
+                    ZoneMessage::UpdateAnimatedCorpseEmotion(_, emotion, cooldown),
+                    _,
+                ) => Some((*emotion, *cooldown)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn moves(messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .filter(|message| {
+                matches!(
+                    message,
+                    Message::Event(SendEventMessage::RequireAnimatedCorpseMove(..), _)
+                )
+            })
+            .count()
+    }
+
+    /// Emotion message given by an animation of animated corpse in given emotion state
+    fn animate(emotion: Emotion, cooldown: u8) -> Vec<(Emotion, u8)> {
+        let mut goat = animated_corpse(2, Type::GOAT, (1, 1));
+        goat.set_emotion(emotion, cooldown);
+        let zone = zone(LINES, vec![goat]);
+        let fear = Fear::from_animated_corpse(&zone.animated_corpses[0]);
+
+        emotions(&fear.on_animate(
+            &zone.animated_corpses[0],
+            &zone,
+            &GameTime { minute_of_day: 0 },
+        ))
+    }
+
+    #[test]
+    fn emotions_cool_down() {
+        let fear = Fear::from_animated_corpse(&animated_corpse(2, Type::GOAT, (1, 1)));
+
+        assert!(animate(Emotion::Calm, 0).is_empty());
+        assert_eq!(animate(Emotion::Alert, 2), vec![(Emotion::Alert, 1)]);
+        assert_eq!(animate(Emotion::Alert, 1), vec![(Emotion::Calm, 0)]);
+        assert_eq!(
+            animate(Emotion::Panic, 1),
+            vec![(Emotion::Recovering, fear.recovering_cooldown)]
+        );
+        assert_eq!(animate(Emotion::Recovering, 1), vec![(Emotion::Calm, 0)]);
+    }
+
+    #[test]
+    fn threat_around_make_calm_ones_alert() {
+        let zone = zone(LINES, vec![animated_corpse(2, Type::GOAT, (1, 5))]);
+        let goat = zone.animated_corpses[0].as_ref();
+        let fear = Fear::from_animated_corpse(&zone.animated_corpses[0]);
+
+        let messages = fear.on_threat(goat, &zone, (1, 1), Some((1, 0)));
+        assert_eq!(
+            emotions(&messages),
+            vec![(Emotion::Alert, fear.alert_cooldown)]
+        );
+        assert_eq!(moves(&messages), 0);
+        // Too far to be noticed
+        assert!(fear.on_threat(goat, &zone, (1, 0), None).is_empty());
+    }
+
+    #[test]
+    fn close_or_fast_threat_make_panic() {
+        let zone = zone(LINES, vec![animated_corpse(2, Type::GOAT, (1, 8))]);
+        let goat = zone.animated_corpses[0].as_ref();
+        let fear = Fear::from_animated_corpse(&zone.animated_corpses[0]);
+
+        // Close one, then one which is further but approached fast
+        for (threat, threat_from) in [((1, 6), None), ((1, 4), Some((1, 0)))].iter() {
+            let messages = fear.on_threat(goat, &zone, *threat, *threat_from);
+            assert_eq!(
+                emotions(&messages),
+                vec![(Emotion::Panic, fear.panic_cooldown)]
+            );
+            assert_eq!(moves(&messages), 1);
+            assert_eq!(alarms(&messages), vec![1.0]);
+        }
+    }
+
+    #[test]
+    fn recovering_ones_flee_as_soon_as_threat_is_around() {
+        let mut goat = animated_corpse(2, Type::GOAT, (1, 5));
+        goat.set_emotion(Emotion::Recovering, 2);
+        let zone = zone(LINES, vec![goat]);
+        let fear = Fear::from_animated_corpse(&zone.animated_corpses[0]);
+
+        let messages = fear.on_threat(zone.animated_corpses[0].as_ref(), &zone, (1, 1), None);
+        assert_eq!(
+            emotions(&messages),
+            vec![(Emotion::Panic, fear.panic_cooldown)]
+        );
+        assert_eq!(moves(&messages), 1);
     }
 }
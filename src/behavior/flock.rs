@@ -0,0 +1,182 @@
+//! Flocking (boids) model for species moving in pack: separation from too close pack mates,
+//! alignment with their heading and cohesion toward their center.
+use crate::ac::AnimatedCorpse;
+use crate::util;
+use crate::zone::Zone;
+
+/// Pack mates further than this distance are ignored
+pub const FLOCK_RADIUS: u32 = 6;
+/// Pack mates at this distance (or less) are too close
+pub const SEPARATION_DISTANCE: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FlockWeights {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+/// Desired move direction (row, col) given pack mates around, None if there is no pack
+/// mate around or if forces cancel each other
+pub fn direction(
+    animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+    zone: &Zone,
+    weights: &FlockWeights,
+) -> Option<(f32, f32)> {
+    let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+    let mut separation = (0.0, 0.0);
+    let mut alignment = (0.0, 0.0);
+    let mut center = (0.0, 0.0);
+    let mut mates_count = 0;
+
+    for mate in zone.animated_corpses.iter().filter(|other| {
+        other.id() != animated_corpse.id() && other.type_() == animated_corpse.type_()
+    }) {
+        let mate_position = (mate.zone_row_i(), mate.zone_col_i());
+        if !util::is_near(position, mate_position, FLOCK_RADIUS) {
+            continue;
+        }
+
+        let offset = (
+            mate_position.0 as f32 - position.0 as f32,
+            mate_position.1 as f32 - position.1 as f32,
+        );
+        mates_count += 1;
+        center.0 += offset.0;
+        center.1 += offset.1;
+        if util::distance(position, mate_position) <= SEPARATION_DISTANCE {
+            separation.0 -= offset.0;
+            separation.1 -= offset.1;
+        }
+        if let Some(heading) = mate.heading() {
+            alignment.0 += heading.0 as f32;
+            alignment.1 += heading.1 as f32;
+        }
+    }
+
+    if mates_count == 0 {
+        return None;
+    }
+
+    let mates_count = mates_count as f32;
+    let direction = (
+        weights.separation * separation.0
+            + weights.alignment * alignment.0 / mates_count
+            + weights.cohesion * center.0 / mates_count,
+        weights.separation * separation.1
+            + weights.alignment * alignment.1 / mates_count
+            + weights.cohesion * center.1 / mates_count,
+    );
+
+    if direction.0.abs() < f32::EPSILON && direction.1.abs() < f32::EPSILON {
+        return None;
+    }
+    Some(direction)
+}
+
+/// Successor which best follow given direction, None if all successors go against it
+pub fn best_successor(
+    position: (u32, u32),
+    successors: &[((u32, u32), u32)],
+    direction: (f32, f32),
+) -> Option<(u32, u32)> {
+    successors
+        .iter()
+        .map(|(successor, _weight)| {
+            let step = (
+                successor.0 as f32 - position.0 as f32,
+                successor.1 as f32 - position.1 as f32,
+            );
+            let length = (step.0 * step.0 + step.1 * step.1).sqrt();
+            let alignment = (step.0 * direction.0 + step.1 * direction.1) / length;
+            (*successor, alignment)
+        })
+        .filter(|(_, alignment)| *alignment > 0.0)
+        .max_by(|(_, alignment1), (_, alignment2)| alignment1.total_cmp(alignment2))
+        .map(|(successor, _)| successor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Type;
+    use crate::zone::tests::{animated_corpse, zone};
+
+    const WEIGHTS: FlockWeights = FlockWeights {
+        separation: 1.0,
+        alignment: 1.0,
+        cohesion: 1.0,
+    };
+    const LINES: &str = "..........\n..........\n..........\n..........\n..........\n";
+
+    #[test]
+    fn direction_without_mates() {
+        let zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (2, 0)),
+                // Not same species
+                animated_corpse(2, Type::PIG, (2, 3)),
+            ],
+        );
+
+        assert_eq!(
+            direction(zone.animated_corpses[0].as_ref(), &zone, &WEIGHTS),
+            None
+        );
+    }
+
+    #[test]
+    fn direction_toward_mates_center() {
+        let zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (2, 0)),
+                animated_corpse(2, Type::GOAT, (2, 4)),
+                // Too far to be considered
+                animated_corpse(3, Type::GOAT, (2, 9)),
+            ],
+        );
+
+        assert_eq!(
+            direction(zone.animated_corpses[0].as_ref(), &zone, &WEIGHTS),
+            Some((0.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn direction_away_from_too_close_mates() {
+        let zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (2, 5)),
+                animated_corpse(2, Type::GOAT, (2, 6)),
+            ],
+        );
+        let weights = FlockWeights {
+            separation: 2.0,
+            ..WEIGHTS
+        };
+
+        let (row, col) = direction(zone.animated_corpses[0].as_ref(), &zone, &weights).unwrap();
+        assert_eq!(row, 0.0);
+        assert!(col < 0.0);
+    }
+
+    #[test]
+    fn best_successor_follow_direction() {
+        let successors = [((1, 1), 1), ((2, 2), 1), ((2, 0), 1)];
+
+        assert_eq!(
+            best_successor((1, 1), &successors[1..], (1.0, 1.0)),
+            Some((2, 2))
+        );
+        assert_eq!(
+            best_successor((1, 1), &successors[1..], (0.0, -1.0)),
+            Some((2, 0))
+        );
+        // Staying still (or going against direction) is not following it
+        assert_eq!(best_successor((1, 1), &successors[..1], (1.0, 0.0)), None);
+        assert_eq!(best_successor((1, 1), &successors[2..], (-1.0, 1.0)), None);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::ac::{AnimatedCorpse, Type, MAX_HUNGER};
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::ZoneEvent;
+use crate::message::{Hunger, Message, SendEventMessage, ZoneMessage};
+use crate::tile::zone::TileCategory;
+use crate::util;
+use crate::zone::Zone;
+
+/// Hunger grows at each animation; when hungry, go to nearest food tile or crop build
+/// and eat it
+pub struct Forage {
+    pub animate_each: u8,
+    /// Hunger added at each animation
+    pub hunger_rate: Hunger,
+    /// Hunger from which animated corpse look for food
+    pub hungry_at: Hunger,
+    /// How far (in tiles) food can be seen
+    pub sight: u32,
+}
+
+impl Forage {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        let (animate_each, hunger_rate) = match animated_corpse.type_() {
+            Type::HARE => (3, 4),
+            Type::GOAT => (5, 3),
+            Type::MOORHEN => (2, 2),
+            Type::PIG => (3, 5),
+            Type::CRAB => (7, 2),
+            Type::FOX => (4, 3),
+        };
+        let sight = match animated_corpse.type_() {
+            Type::HARE => 6,
+            Type::GOAT => 5,
+            Type::MOORHEN => 4,
+            Type::PIG => 5,
+            Type::CRAB => 2,
+            Type::FOX => 4,
+        };
+        Self {
+            animate_each,
+            hunger_rate,
+            hungry_at: MAX_HUNGER / 2,
+            sight,
+        }
+    }
+
+    /// Positions of food tiles and crop builds in sight, nearest first
+    fn foods_in_sight(&self, zone: &Zone, position: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut foods: Vec<(u32, u32)> = zone
+            .tiles_near(TileCategory::Food, position, self.sight)
+            .into_iter()
+            .chain(
+                zone.builds
+                    .iter()
+                    .filter(|build| build.is_crop())
+                    .map(|build| (build.row_i, build.col_i))
+                    .filter(|build_position| util::is_near(position, *build_position, self.sight)),
+            )
+            .collect();
+        foods.sort_by_key(|food_position| util::distance(position, *food_position));
+        foods
+    }
+}
+
+impl Behavior for Forage {
+    fn name(&self) -> &str {
+        "forage"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        Some(self.animate_each)
+    }
+
+    fn on_event(
+        &self,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _event: &ZoneEvent,
+        _zone: &Zone,
+    ) -> Vec<Message> {
+        vec![]
+    }
+
+    fn on_animate(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        let mut messages: Vec<Message> = vec![];
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        let zone_coordinates = (animated_corpse.world_row_i(), animated_corpse.world_col_i());
+        let hunger = animated_corpse
+            .hunger()
+            .saturating_add(self.hunger_rate)
+            .min(MAX_HUNGER);
+
+        if hunger >= self.hungry_at && !animated_corpse.asleep() {
+            let foods = self.foods_in_sight(zone, position);
+            match foods.first() {
+                // Food (on tile or around, as crops can be not traversable) is reached
+                Some(food_position) if util::is_near(position, *food_position, 1) => {
+                    messages.push(Message::Event(
+                        SendEventMessage::AnimatedCorpseEat(
+                            animated_corpse.id(),
+                            food_position.0,
+                            food_position.1,
+                        ),
+                        zone_coordinates,
+                    ));
+                    messages.push(Message::Zone(
+                        ZoneMessage::UpdateAnimatedCorpseHunger(animated_corpse.id(), 0),
+                        zone_coordinates,
+                    ));
+                    return messages;
+                }
+                // Alert or panicking ones don't walk to it
+                Some(_) if animated_corpse.emotion().holds_still() => {}
+                Some(food_position) => {
+                    if let Some((move_to_row_i, move_to_col_i)) =
+                        zone.step_toward(animated_corpse.as_ref(), *food_position)
+                    {
+                        messages.push(Message::Event(
+                            SendEventMessage::RequireAnimatedCorpseMove(
+                                animated_corpse.id(),
+                                move_to_row_i,
+                                move_to_col_i,
+                            ),
+                            zone_coordinates,
+                        ));
+                    }
+                }
+                // No food in sight, let random moves find some
+                None => {}
+            }
+        }
+
+        if hunger != animated_corpse.hunger() {
+            messages.push(Message::Zone(
+                ZoneMessage::UpdateAnimatedCorpseHunger(animated_corpse.id(), hunger),
+                zone_coordinates,
+            ));
+        }
+
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Emotion;
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const TIME: GameTime = GameTime { minute_of_day: 0 };
+
+    fn hare(hunger: Hunger, position: (u32, u32)) -> Box<dyn AnimatedCorpse + Send + Sync> {
+        let mut hare = animated_corpse(1, Type::HARE, position);
+        hare.set_hunger(hunger);
+        hare
+    }
+
+    fn animate(lines: &str, hare: Box<dyn AnimatedCorpse + Send + Sync>) -> Vec<Message> {
+        let zone = zone(lines, vec![hare]);
+        let forage = Forage::from_animated_corpse(zone.animated_corpses[0].as_ref());
+        forage.on_animate(&zone.animated_corpses[0], &zone, &TIME)
+    }
+
+    fn hungers(messages: &[Message]) -> Vec<Hunger> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(ZoneMessage::UpdateAnimatedCorpseHunger(_, hunger), _) => {
+                    Some(*hunger)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn eats(messages: &[Message]) -> Vec<(u32, u32)> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Event(SendEventMessage::AnimatedCorpseEat(_, row_i, col_i), _) => {
+                    Some((*row_i, *col_i))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hunger_grows_and_is_capped() {
+        assert_eq!(hungers(&animate(",,,\n", hare(0, (0, 1)))), vec![4]);
+        assert_eq!(
+            hungers(&animate(",,,\n", hare(MAX_HUNGER - 1, (0, 1)))),
+            vec![MAX_HUNGER]
+        );
+        assert!(animate(",,,\n", hare(MAX_HUNGER, (0, 1))).is_empty());
+    }
+
+    #[test]
+    fn hungry_one_steps_toward_nearest_food() {
+        let messages = animate(".,,,,,,.\n", hare(60, (0, 3)));
+
+        assert_eq!(moves(&messages), vec![(0, 2)]);
+        assert_eq!(hungers(&messages), vec![64]);
+        // Not hungry yet
+        assert!(moves(&animate(".,,,,,,.\n", hare(0, (0, 3)))).is_empty());
+    }
+
+    #[test]
+    fn eat_on_food_tile() {
+        let messages = animate(".,,\n", hare(60, (0, 0)));
+
+        assert_eq!(eats(&messages), vec![(0, 0)]);
+        assert_eq!(hungers(&messages), vec![0]);
+        assert!(moves(&messages).is_empty());
+    }
+
+    #[test]
+    fn asleep_or_holding_still_ones_stay() {
+        let mut asleep = hare(60, (0, 3));
+        asleep.set_asleep(true);
+        let messages = animate(".,,,,,,.\n", asleep);
+        assert!(moves(&messages).is_empty());
+        assert!(eats(&messages).is_empty());
+        assert_eq!(hungers(&messages), vec![64]);
+
+        for emotion in [Emotion::Alert, Emotion::Panic] {
+            let mut still = hare(60, (0, 3));
+            still.set_emotion(emotion, 1);
+            let messages = animate(".,,,,,,.\n", still);
+            assert!(moves(&messages).is_empty());
+            assert!(eats(&messages).is_empty());
+        }
+    }
+}
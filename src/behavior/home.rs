@@ -0,0 +1,115 @@
+use crate::ac::AnimatedCorpse;
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::ZoneEvent;
+use crate::message::{Message, SendEventMessage};
+use crate::util;
+use crate::zone::Zone;
+
+/// Go back toward home when outside home range (like after fleeing)
+pub struct Home {
+    pub animate_each: u8,
+    pub range: u32,
+}
+
+impl Home {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        Self {
+            animate_each: animated_corpse.traits().scale_animate_each(3),
+            range: animated_corpse.type_().home_range(),
+        }
+    }
+}
+
+impl Behavior for Home {
+    fn name(&self) -> &str {
+        "home"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        Some(self.animate_each)
+    }
+
+    fn on_event(
+        &self,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _event: &ZoneEvent,
+        _zone: &Zone,
+    ) -> Vec<Message> {
+        vec![]
+    }
+
+    fn on_animate(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        let home = animated_corpse.home();
+        if animated_corpse.asleep()
+            || animated_corpse.emotion().holds_still()
+            || util::is_near(position, home, self.range)
+        {
+            return vec![];
+        }
+        // Herd followers follow their leader instead
+        if animated_corpse.type_().moves_in_pack()
+            && zone.herd_leader(animated_corpse.as_ref()).is_some()
+        {
+            return vec![];
+        }
+
+        match zone.step_toward(animated_corpse.as_ref(), home) {
+            Some((move_to_row_i, move_to_col_i)) => vec![Message::Event(
+                SendEventMessage::RequireAnimatedCorpseMove(
+                    animated_corpse.id(),
+                    move_to_row_i,
+                    move_to_col_i,
+                ),
+                (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
+            )],
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::{Emotion, Type};
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const TIME: GameTime = GameTime { minute_of_day: 0 };
+
+    /// Moves of an animation of animated corpse of given type, living at (0, 0)
+    fn animate(type_: Type, position: (u32, u32), emotion: Emotion) -> Vec<(u32, u32)> {
+        let mut animated_corpse = animated_corpse(1, type_, position);
+        animated_corpse.base_mut().home = Some((0, 0));
+        animated_corpse.set_emotion(emotion, 1);
+        let zone = zone("............\n", vec![animated_corpse]);
+        let home = Home::from_animated_corpse(zone.animated_corpses[0].as_ref());
+
+        moves(&home.on_animate(&zone.animated_corpses[0], &zone, &TIME))
+    }
+
+    #[test]
+    fn outside_home_range_step_back_toward_home() {
+        assert_eq!(Type::CRAB.home_range(), 2);
+        assert_eq!(animate(Type::CRAB, (0, 5), Emotion::Calm), vec![(0, 4)]);
+        assert_eq!(Type::FOX.home_range(), 10);
+        assert_eq!(animate(Type::FOX, (0, 11), Emotion::Calm), vec![(0, 10)]);
+    }
+
+    #[test]
+    fn inside_home_range_stay() {
+        assert!(animate(Type::CRAB, (0, 2), Emotion::Calm).is_empty());
+        assert!(animate(Type::FOX, (0, 10), Emotion::Calm).is_empty());
+    }
+
+    #[test]
+    fn holding_still_ones_stay() {
+        assert!(animate(Type::CRAB, (0, 5), Emotion::Alert).is_empty());
+        assert!(animate(Type::CRAB, (0, 5), Emotion::Panic).is_empty());
+    }
+}
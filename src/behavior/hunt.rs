@@ -0,0 +1,221 @@
+use crate::ac::{AnimatedCorpse, Relation, Type};
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::{ZoneEvent, ZoneEventType};
+use crate::message::{Message, SendEventMessage};
+use crate::util;
+use crate::zone::Zone;
+
+/// Chase animated corpses of hunted species (see `ac::RELATIONS`) when they are in sight
+pub struct Hunt {
+    pub animate_each: u8,
+    /// How far (in tiles) preys can be seen
+    pub sight: u32,
+}
+
+impl Hunt {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        let (animate_each, sight) = match animated_corpse.type_() {
+            Type::FOX => (2, 6),
+            _ => (3, 4),
+        };
+        Self {
+            animate_each,
+            sight,
+        }
+    }
+
+    /// Move one step toward prey at given position, if in sight
+    fn chase(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        zone: &Zone,
+        prey: (u32, u32),
+    ) -> Vec<Message> {
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        if animated_corpse.asleep()
+            || animated_corpse.emotion().holds_still()
+            || !util::is_near(position, prey, self.sight)
+        {
+            return vec![];
+        }
+
+        zone.step_toward(animated_corpse, prey)
+            .map(|(move_to_row_i, move_to_col_i)| {
+                vec![Message::Event(
+                    SendEventMessage::RequireAnimatedCorpseMove(
+                        animated_corpse.id(),
+                        move_to_row_i,
+                        move_to_col_i,
+                    ),
+                    (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
+                )]
+            })
+            .unwrap_or_default()
+    }
+
+    fn hunts(&self, animated_corpse: &(dyn AnimatedCorpse + Send + Sync), other: Type) -> bool {
+        animated_corpse.type_().relation_to(&other) == Relation::Hunts
+    }
+}
+
+impl Behavior for Hunt {
+    fn name(&self) -> &str {
+        "hunt"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        Some(self.animate_each)
+    }
+
+    fn on_event(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        event: &ZoneEvent,
+        zone: &Zone,
+    ) -> Vec<Message> {
+        match &event.event_type {
+            ZoneEventType::AnimatedCorpseMove {
+                to_row_i,
+                to_col_i,
+                animated_corpse_id,
+            } => match zone.animated_corpse(*animated_corpse_id) {
+                Some(other) if self.hunts(animated_corpse.as_ref(), other.type_()) => {
+                    self.chase(animated_corpse.as_ref(), zone, (*to_row_i, *to_col_i))
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
+    fn on_animate(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        let nearest_prey = zone
+            .animated_corpses
+            .iter()
+            .filter(|other| self.hunts(animated_corpse.as_ref(), other.type_()))
+            .map(|other| (other.zone_row_i(), other.zone_col_i()))
+            .min_by_key(|prey| util::distance(position, *prey));
+
+        match nearest_prey {
+            Some(prey) => self.chase(animated_corpse.as_ref(), zone, prey),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Emotion;
+    use crate::behavior::fear::Fear;
+    use crate::message::ZoneMessage;
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const LINE: &str = "............\n";
+    const TIME: GameTime = GameTime { minute_of_day: 0 };
+
+    fn move_event(animated_corpse_id: u32, to: (u32, u32)) -> ZoneEvent {
+        ZoneEvent {
+            event_type: ZoneEventType::AnimatedCorpseMove {
+                to_row_i: to.0,
+                to_col_i: to.1,
+                animated_corpse_id,
+            },
+            event_type_name: "animated_corpse_move".to_string(),
+            world_row_i: 0,
+            world_col_i: 0,
+        }
+    }
+
+    #[test]
+    fn fox_steps_toward_nearest_prey() {
+        let zone = zone(
+            LINE,
+            vec![
+                animated_corpse(1, Type::FOX, (0, 5)),
+                // Nearer, but not preys
+                animated_corpse(2, Type::GOAT, (0, 4)),
+                animated_corpse(3, Type::PIG, (0, 7)),
+                animated_corpse(4, Type::HARE, (0, 1)),
+                animated_corpse(5, Type::MOORHEN, (0, 8)),
+            ],
+        );
+        let fox = &zone.animated_corpses[0];
+        let hunt = Hunt::from_animated_corpse(fox.as_ref());
+
+        assert_eq!(moves(&hunt.on_animate(fox, &zone, &TIME)), vec![(0, 6)]);
+    }
+
+    #[test]
+    fn fox_ignores_non_preys() {
+        let zone = zone(
+            LINE,
+            vec![
+                animated_corpse(1, Type::FOX, (0, 5)),
+                animated_corpse(2, Type::GOAT, (0, 7)),
+                animated_corpse(3, Type::PIG, (0, 2)),
+                animated_corpse(4, Type::FOX, (0, 9)),
+            ],
+        );
+        let fox = &zone.animated_corpses[0];
+        let hunt = Hunt::from_animated_corpse(fox.as_ref());
+
+        assert!(hunt.on_animate(fox, &zone, &TIME).is_empty());
+        assert!(hunt.on_event(fox, &move_event(2, (0, 6)), &zone).is_empty());
+        assert!(hunt.on_event(fox, &move_event(4, (0, 8)), &zone).is_empty());
+    }
+
+    #[test]
+    fn fox_chases_moving_prey_in_sight() {
+        let zone = zone(
+            LINE,
+            vec![
+                animated_corpse(1, Type::FOX, (0, 2)),
+                animated_corpse(2, Type::HARE, (0, 5)),
+            ],
+        );
+        let fox = &zone.animated_corpses[0];
+        let hunt = Hunt::from_animated_corpse(fox.as_ref());
+
+        assert_eq!(
+            moves(&hunt.on_event(fox, &move_event(2, (0, 6)), &zone)),
+            vec![(0, 3)]
+        );
+        // Out of sight
+        assert!(hunt
+            .on_event(fox, &move_event(2, (0, 11)), &zone)
+            .is_empty());
+    }
+
+    #[test]
+    fn preys_flee_moving_fox() {
+        for type_ in [Type::HARE, Type::MOORHEN] {
+            let zone = zone(
+                LINE,
+                vec![
+                    animated_corpse(1, Type::FOX, (0, 3)),
+                    animated_corpse(2, type_, (0, 5)),
+                ],
+            );
+            let prey = &zone.animated_corpses[1];
+            let fear = Fear::from_animated_corpse(prey);
+
+            let messages = fear.on_event(prey, &move_event(1, (0, 4)), &zone);
+            assert_eq!(moves(&messages), vec![(0, 6)]);
+            assert!(messages.iter().any(|message| matches!(
+                message,
+                Message::Zone(
+                    ZoneMessage::UpdateAnimatedCorpseEmotion(2, Emotion::Panic, _),
+                    _
+                )
+            )));
+        }
+    }
+}
@@ -1,31 +1,56 @@
-use crate::ac::{AnimatedCorpse, Type};
+use crate::ac::AnimatedCorpse;
+use crate::clock::GameTime;
 use crate::event::ZoneEvent;
-use crate::message::Message;
+use crate::message::{Alarm, Message};
 use crate::zone::Zone;
 
+pub mod drink;
 pub mod fear;
+pub mod flock;
+pub mod forage;
+pub mod home;
+pub mod hunt;
 pub mod move_;
+pub mod sleep;
+pub mod territory;
 
+/// Behaviors of given animated corpse. Needs come first: when several behaviors require a
+/// move at same animation, the first one wins (see `Zone::accept_event_message`).
 pub fn get_behaviors_for(
     animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
 ) -> Vec<Box<dyn Behavior + Send + Sync>> {
-    match animated_corpse.type_() {
-        Type::HARE => {
-            vec![
-                Box::new(move_::Move::from_animated_corpse(animated_corpse)),
-                Box::new(fear::Fear::from_animated_corpse(animated_corpse)),
-            ]
-        }
-        _ => {
-            vec![
-                Box::new(move_::Move::from_animated_corpse(animated_corpse)),
-                Box::new(fear::Fear::from_animated_corpse(animated_corpse)),
-            ]
-        }
+    let type_ = animated_corpse.type_();
+    let mut behaviors: Vec<Box<dyn Behavior + Send + Sync>> = vec![Box::new(
+        sleep::Sleep::from_animated_corpse(animated_corpse.as_ref()),
+    )];
+    if type_.hunts() {
+        behaviors.push(Box::new(hunt::Hunt::from_animated_corpse(
+            animated_corpse.as_ref(),
+        )));
+    } else {
+        behaviors.push(Box::new(forage::Forage::from_animated_corpse(
+            animated_corpse.as_ref(),
+        )));
+    }
+    behaviors.push(Box::new(drink::Drink::from_animated_corpse(
+        animated_corpse.as_ref(),
+    )));
+    behaviors.push(Box::new(home::Home::from_animated_corpse(
+        animated_corpse.as_ref(),
+    )));
+    behaviors.push(Box::new(move_::Move::from_animated_corpse(animated_corpse)));
+    if type_.territorial() {
+        behaviors.push(Box::new(territory::Territory::from_animated_corpse(
+            animated_corpse.as_ref(),
+        )));
+    } else {
+        behaviors.push(Box::new(fear::Fear::from_animated_corpse(animated_corpse)));
     }
+    behaviors
 }
 
 pub trait Behavior {
+    fn name(&self) -> &str;
     fn animate_each(&self) -> Option<u8>;
     fn on_event(
         &self,
@@ -37,5 +62,47 @@ pub trait Behavior {
         &self,
         animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
         zone: &Zone,
+        time: &GameTime,
     ) -> Vec<Message>;
+    /// React to an alarm raised by another animated corpse of zone
+    fn on_alarm(
+        &self,
+        _animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        _alarm: &Alarm,
+        _zone: &Zone,
+    ) -> Vec<Message> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Type;
+    use crate::zone::tests::animated_corpse;
+
+    fn names(type_: Type) -> Vec<String> {
+        get_behaviors_for(&animated_corpse(1, type_, (0, 0)))
+            .iter()
+            .map(|behavior| behavior.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn behaviors_by_type() {
+        for type_ in [Type::HARE, Type::GOAT, Type::MOORHEN, Type::PIG] {
+            assert_eq!(
+                names(type_),
+                vec!["sleep", "forage", "drink", "home", "move", "fear"]
+            );
+        }
+        assert_eq!(
+            names(Type::FOX),
+            vec!["sleep", "hunt", "drink", "home", "move", "fear"]
+        );
+        assert_eq!(
+            names(Type::CRAB),
+            vec!["sleep", "forage", "drink", "home", "move", "territory"]
+        );
+    }
 }
@@ -1,63 +1,85 @@
 use crate::ac::{AnimatedCorpse, Type};
+use crate::behavior::flock::{self, FlockWeights};
 use crate::behavior::Behavior;
-use crate::event::{ZoneEvent, ZoneEventType};
+use crate::clock::GameTime;
+use crate::event::ZoneEvent;
+use crate::grid::GridPosition;
 use crate::message::{Message, SendEventMessage};
 use crate::util;
 use crate::zone::Zone;
 use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Followers pick destinations at this distance (or less) of their herd leader
+pub const FOLLOW_DISTANCE: u32 = 2;
+
+/// Chance weight of wandering to given position: 1.0 inside home range, then shrinking
+/// with (squared) distance outside it
+pub fn home_weight(home: GridPosition, home_range: u32, position: GridPosition) -> f64 {
+    let outside = util::distance(home, position).saturating_sub(home_range);
+    1.0 / ((1 + outside) as f64).powi(2)
+}
 
 pub struct Move {
     pub animate_each: u8,
     pub move_in_pack: bool,
+    /// Chance to move at each animation
+    pub wander_chance: f64,
+    /// Chance to follow pack (flocking) instead of moving randomly (if move in pack)
+    pub pack_chance: f64,
+    pub flock: FlockWeights,
 }
 
 impl Move {
     pub fn from_animated_corpse(animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>) -> Self {
-        let move_in_pack = match animated_corpse.type_() {
-            Type::HARE => true,
-            Type::GOAT => true,
-            Type::MOORHEN => true,
-            Type::PIG => true,
-            Type::CRAB => false,
-        };
+        let move_in_pack = animated_corpse.type_().moves_in_pack();
         let animate_each = match animated_corpse.type_() {
             Type::HARE => 3,
             Type::GOAT => 5,
             Type::MOORHEN => 2,
             Type::PIG => 3,
             Type::CRAB => 7,
+            Type::FOX => 3,
         };
+        let (separation, alignment) = match animated_corpse.type_() {
+            // Tight groups heading together
+            Type::MOORHEN => (1.5, 1.5),
+            // Loose herds
+            Type::GOAT => (2.0, 1.0),
+            _ => (1.0, 1.0),
+        };
+        let traits = animated_corpse.traits();
         Self {
             move_in_pack,
-            animate_each,
+            animate_each: traits.scale_animate_each(animate_each),
+            wander_chance: 0.5 + traits.curiosity as f64 / 2.0,
+            pack_chance: traits.sociability as f64,
+            flock: FlockWeights {
+                separation,
+                alignment,
+                // Sociable ones stay nearer to pack center
+                cohesion: 0.5 + traits.sociability,
+            },
         }
     }
 }
 
 impl Behavior for Move {
+    fn name(&self) -> &str {
+        "move"
+    }
+
     fn animate_each(&self) -> Option<u8> {
         Some(self.animate_each)
     }
 
     fn on_event(
         &self,
-        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
-        event: &ZoneEvent,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _event: &ZoneEvent,
         _zone: &Zone,
     ) -> Vec<Message> {
-        match &event.event_type {
-            ZoneEventType::AnimatedCorpseMove {
-                to_row_i: _,
-                to_col_i: _,
-                animated_corpse_id,
-            } => {
-                if animated_corpse_id != &animated_corpse.base().id && self.move_in_pack {
-                    // TODO Moving in a pack
-                };
-            }
-            _ => {}
-        }
-
+        // Pack moves are decided at animation (see flock)
         vec![]
     }
 
@@ -65,19 +87,81 @@ impl Behavior for Move {
         &self,
         animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
         zone: &Zone,
+        _time: &GameTime,
     ) -> Vec<Message> {
         let mut messages: Vec<Message> = vec![];
 
-        if let Some(((move_to_row_i, move_to_col_i), _weight)) = zone
-            .get_successors(animated_corpse.zone_row_i(), animated_corpse.zone_col_i())
-            .choose(&mut rand::thread_rng())
+        let mut rng = rand::thread_rng();
+
+        if animated_corpse.linger() > 0
+            || animated_corpse.asleep()
+            || animated_corpse.emotion().holds_still()
+            || !rng.gen_bool(self.wander_chance)
         {
+            return messages;
+        }
+
+        let leader = if self.move_in_pack {
+            zone.herd_leader(animated_corpse.as_ref())
+        } else {
+            None
+        };
+        let successors: Vec<((u32, u32), u32)> = match leader {
+            // Followers stay near their leader, wherever it goes
+            Some(leader) => {
+                let leader_position = (leader.zone_row_i(), leader.zone_col_i());
+                let near_leader: Vec<((u32, u32), u32)> = zone
+                    .get_free_successors(animated_corpse.as_ref())
+                    .into_iter()
+                    .filter(|(successor, _weight)| {
+                        util::is_near(leader_position, *successor, FOLLOW_DISTANCE)
+                    })
+                    .collect();
+                if near_leader.is_empty() {
+                    zone.step_toward(animated_corpse.as_ref(), leader_position)
+                        .map(|successor| vec![(successor, 1)])
+                        .unwrap_or_default()
+                } else {
+                    near_leader
+                }
+            }
+            None => zone.get_free_successors(animated_corpse.as_ref()),
+        };
+        // Without leader, wander preferably inside home range (home behavior bring back
+        // animated corpses gone too far)
+        let home = animated_corpse.home();
+        let home_range = animated_corpse.type_().home_range();
+        let successor_weight = |successor: GridPosition| match leader {
+            Some(_) => 1.0,
+            None => home_weight(home, home_range, successor),
+        };
+        let flock_direction = if self.move_in_pack && rng.gen_bool(self.pack_chance) {
+            flock::direction(animated_corpse.as_ref(), zone, &self.flock)
+        } else {
+            None
+        };
+        let move_to = match flock_direction {
+            // Staying still is better than going against pack
+            Some(direction) => flock::best_successor(
+                (animated_corpse.zone_row_i(), animated_corpse.zone_col_i()),
+                &successors,
+                direction,
+            ),
+            None => successors
+                .choose_weighted(&mut rng, |(successor, _weight)| {
+                    successor_weight(*successor)
+                })
+                .ok()
+                .map(|(successor, _weight)| *successor),
+        };
+
+        if let Some((move_to_row_i, move_to_col_i)) = move_to {
             let mut near_character = false;
             for character in &zone.characters {
                 // FIXME: impelment retry (5 times for example)
                 if util::is_near(
                     (character.zone_row_i, character.zone_col_i),
-                    (*move_to_row_i, *move_to_col_i),
+                    (move_to_row_i, move_to_col_i),
                     2,
                 ) {
                     near_character = true;
@@ -88,8 +172,8 @@ impl Behavior for Move {
                 messages.push(Message::Event(
                     SendEventMessage::RequireAnimatedCorpseMove(
                         animated_corpse.id(),
-                        *move_to_row_i,
-                        *move_to_col_i,
+                        move_to_row_i,
+                        move_to_col_i,
                     ),
                     (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
                 ));
@@ -99,3 +183,19 @@ impl Behavior for Move {
         messages
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_weight_shrink_outside_home_range() {
+        let home = (10, 10);
+
+        assert_eq!(home_weight(home, 3, (10, 10)), 1.0);
+        assert_eq!(home_weight(home, 3, (13, 10)), 1.0);
+        assert_eq!(home_weight(home, 3, (14, 10)), 0.25);
+        assert!(home_weight(home, 3, (15, 10)) < home_weight(home, 3, (14, 10)));
+        assert!(home_weight(home, 3, (30, 30)) > 0.0);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::ac::{Activity, AnimatedCorpse};
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::ZoneEvent;
+use crate::message::{Message, ZoneMessage};
+use crate::zone::Zone;
+
+/// Fall asleep when species rest (night for diurnal ones, day for nocturnal ones) and wake
+/// up when it is active. Asleep animated corpses don't move and are less fearful.
+pub struct Sleep {
+    pub activity: Activity,
+}
+
+impl Sleep {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        Self {
+            activity: animated_corpse.type_().activity(),
+        }
+    }
+}
+
+impl Behavior for Sleep {
+    fn name(&self) -> &str {
+        "sleep"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        Some(1)
+    }
+
+    fn on_event(
+        &self,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _event: &ZoneEvent,
+        _zone: &Zone,
+    ) -> Vec<Message> {
+        vec![]
+    }
+
+    fn on_animate(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _zone: &Zone,
+        time: &GameTime,
+    ) -> Vec<Message> {
+        let asleep = match self.activity {
+            Activity::Diurnal => time.is_night(),
+            Activity::Nocturnal => !time.is_night(),
+        };
+
+        if asleep == animated_corpse.asleep() {
+            return vec![];
+        }
+
+        vec![Message::Zone(
+            ZoneMessage::UpdateAnimatedCorpseAsleep(animated_corpse.id(), asleep),
+            (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::{Type, MAX_HUNGER, MAX_THIRST};
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const NOON: GameTime = GameTime {
+        minute_of_day: 12 * 60,
+    };
+    const MIDNIGHT: GameTime = GameTime { minute_of_day: 0 };
+
+    /// Asleep state required by an animation of given species at given time
+    fn animate(type_: Type, asleep: bool, time: &GameTime) -> Vec<bool> {
+        let mut animated_corpse = animated_corpse(1, type_, (0, 0));
+        animated_corpse.set_asleep(asleep);
+        let zone = zone("...\n", vec![animated_corpse]);
+        let sleep = Sleep::from_animated_corpse(zone.animated_corpses[0].as_ref());
+
+        sleep
+            .on_animate(&zone.animated_corpses[0], &zone, time)
+            .iter()
+            .filter_map(|message| match message {
+                Message::Zone(ZoneMessage::UpdateAnimatedCorpseAsleep(_, asleep), _) => {
+                    Some(*asleep)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diurnal_ones_sleep_at_night() {
+        assert_eq!(animate(Type::GOAT, false, &MIDNIGHT), vec![true]);
+        assert_eq!(animate(Type::GOAT, true, &NOON), vec![false]);
+        assert!(animate(Type::GOAT, true, &MIDNIGHT).is_empty());
+        assert!(animate(Type::GOAT, false, &NOON).is_empty());
+    }
+
+    #[test]
+    fn nocturnal_ones_sleep_at_day() {
+        assert_eq!(animate(Type::HARE, false, &NOON), vec![true]);
+        assert_eq!(animate(Type::HARE, true, &MIDNIGHT), vec![false]);
+        assert!(animate(Type::HARE, true, &NOON).is_empty());
+        assert!(animate(Type::HARE, false, &MIDNIGHT).is_empty());
+    }
+
+    #[test]
+    fn asleep_ones_dont_move() {
+        // Hungry and thirsty, far from home, food and water
+        let zone = |asleep: bool| {
+            let mut goat = animated_corpse(1, Type::GOAT, (0, 9));
+            goat.set_asleep(asleep);
+            goat.set_hunger(MAX_HUNGER);
+            goat.set_thirst(MAX_THIRST);
+            goat.base_mut().home = Some((0, 0));
+            zone("~.,,,,,,,,\n", vec![goat])
+        };
+
+        let asleep = zone(true);
+        assert!((0..60).all(|tick_count| moves(&asleep.animate(tick_count, &MIDNIGHT)).is_empty()));
+        let awake = zone(false);
+        assert!(!moves(&awake.animate(0, &NOON)).is_empty());
+    }
+}
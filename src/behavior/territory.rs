@@ -0,0 +1,183 @@
+use crate::ac::AnimatedCorpse;
+use crate::behavior::Behavior;
+use crate::clock::GameTime;
+use crate::event::{ZoneEvent, ZoneEventType};
+use crate::message::{Message, SendEventMessage};
+use crate::util;
+use crate::zone::Zone;
+
+/// Guard home range: instead of fleeing, confront characters and other animated corpses
+/// (of its own species too) entering it
+pub struct Territory {
+    pub range: u32,
+}
+
+impl Territory {
+    pub fn from_animated_corpse(animated_corpse: &(dyn AnimatedCorpse + Send + Sync)) -> Self {
+        Self {
+            range: animated_corpse.type_().home_range(),
+        }
+    }
+
+    /// Step toward intruder at given position, if it is inside territory
+    fn confront(
+        &self,
+        animated_corpse: &(dyn AnimatedCorpse + Send + Sync),
+        zone: &Zone,
+        intruder: (u32, u32),
+    ) -> Vec<Message> {
+        let home = animated_corpse.home();
+        if animated_corpse.asleep() || !util::is_near(home, intruder, self.range) {
+            return vec![];
+        }
+
+        zone.step_toward(animated_corpse, intruder)
+            // Don't leave territory to chase intruder
+            .filter(|move_to| util::is_near(home, *move_to, self.range))
+            .map(|(move_to_row_i, move_to_col_i)| {
+                vec![Message::Event(
+                    SendEventMessage::RequireAnimatedCorpseMove(
+                        animated_corpse.id(),
+                        move_to_row_i,
+                        move_to_col_i,
+                    ),
+                    (animated_corpse.world_row_i(), animated_corpse.world_col_i()),
+                )]
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Behavior for Territory {
+    fn name(&self) -> &str {
+        "territory"
+    }
+
+    fn animate_each(&self) -> Option<u8> {
+        None
+    }
+
+    fn on_event(
+        &self,
+        animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        event: &ZoneEvent,
+        zone: &Zone,
+    ) -> Vec<Message> {
+        match &event.event_type {
+            ZoneEventType::PlayerMove {
+                to_row_i,
+                to_col_i,
+                character_id: _,
+            } => self.confront(animated_corpse.as_ref(), zone, (*to_row_i, *to_col_i)),
+            ZoneEventType::AnimatedCorpseMove {
+                to_row_i,
+                to_col_i,
+                animated_corpse_id,
+            } => match zone.animated_corpse(*animated_corpse_id) {
+                Some(other) if other.id() != animated_corpse.id() => {
+                    self.confront(animated_corpse.as_ref(), zone, (*to_row_i, *to_col_i))
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
+    fn on_animate(
+        &self,
+        _animated_corpse: &Box<dyn AnimatedCorpse + Send + Sync>,
+        _zone: &Zone,
+        _time: &GameTime,
+    ) -> Vec<Message> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Type;
+    use crate::zone::tests::{animated_corpse, moves, zone};
+
+    const LINE: &str = "..........\n";
+
+    fn event(event_type: ZoneEventType) -> ZoneEvent {
+        ZoneEvent {
+            event_type,
+            event_type_name: "move".to_string(),
+            world_row_i: 0,
+            world_col_i: 0,
+        }
+    }
+
+    fn animated_corpse_move(animated_corpse_id: u32, to: (u32, u32)) -> ZoneEvent {
+        event(ZoneEventType::AnimatedCorpseMove {
+            to_row_i: to.0,
+            to_col_i: to.1,
+            animated_corpse_id,
+        })
+    }
+
+    fn crabs(asleep: bool) -> Zone {
+        let mut crab = animated_corpse(1, Type::CRAB, (0, 2));
+        crab.base_mut().home = Some((0, 2));
+        crab.set_asleep(asleep);
+        zone(
+            LINE,
+            vec![
+                crab,
+                animated_corpse(2, Type::CRAB, (0, 7)),
+                animated_corpse(3, Type::HARE, (0, 8)),
+            ],
+        )
+    }
+
+    #[test]
+    fn crab_confronts_intruders_inside_territory() {
+        let zone = crabs(false);
+        let crab = &zone.animated_corpses[0];
+        let territory = Territory::from_animated_corpse(crab.as_ref());
+
+        for intruder_id in [2, 3] {
+            assert_eq!(
+                moves(&territory.on_event(crab, &animated_corpse_move(intruder_id, (0, 4)), &zone)),
+                vec![(0, 3)]
+            );
+        }
+        let player_move = event(ZoneEventType::PlayerMove {
+            to_row_i: 0,
+            to_col_i: 0,
+            character_id: "character".to_string(),
+        });
+        assert_eq!(
+            moves(&territory.on_event(crab, &player_move, &zone)),
+            vec![(0, 1)]
+        );
+    }
+
+    #[test]
+    fn crab_ignores_intruders_outside_territory() {
+        let zone = crabs(false);
+        let crab = &zone.animated_corpses[0];
+        let territory = Territory::from_animated_corpse(crab.as_ref());
+
+        assert!(territory
+            .on_event(crab, &animated_corpse_move(2, (0, 6)), &zone)
+            .is_empty());
+        // Its own moves
+        assert!(territory
+            .on_event(crab, &animated_corpse_move(1, (0, 3)), &zone)
+            .is_empty());
+    }
+
+    #[test]
+    fn asleep_crab_doesnt_confront() {
+        let zone = crabs(true);
+        let crab = &zone.animated_corpses[0];
+        let territory = Territory::from_animated_corpse(crab.as_ref());
+
+        assert!(territory
+            .on_event(crab, &animated_corpse_move(2, (0, 4)), &zone)
+            .is_empty());
+    }
+}
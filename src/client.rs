@@ -1,16 +1,17 @@
 use reqwest;
-use reqwest::blocking::Response;
+use reqwest::blocking::{RequestBuilder, Response};
 
 use crate::ac::{animated_corpse_from_value, AnimatedCorpse};
+use crate::auth::Auth;
 use crate::model;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 use std::error::Error;
 use std::fmt;
+use std::thread;
+use std::time::Duration;
 use reqwest::Method;
 
-const HEADER_NAME__DISABLE_AUTH_TOKEN: &str = "DISABLE_AUTH_TOKEN";
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ErrorResponse {
     pub message: String,
@@ -21,6 +22,8 @@ pub enum ClientError {
     NotFound { message: String },
     ClientSideError { message: String },
     ServerSideError { message: String },
+    /// Server can't be reached or didn't respond in time
+    Unavailable { message: String },
     InternalError { message: String },
 }
 
@@ -36,15 +39,32 @@ impl ClientError {
             ClientError::ServerSideError { message } => {
                 format!("Server side error: {}", message).to_string()
             }
+            ClientError::Unavailable { message } => {
+                format!("Unavailable: {}", message).to_string()
+            }
             ClientError::InternalError { message } => {
                 format!("Internal error: {}", message).to_string()
             }
         };
     }
+
+    /// Server side and availability errors can be transient, so request can be retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ClientError::ServerSideError { .. } | ClientError::Unavailable { .. }
+        )
+    }
 }
 
 impl From<reqwest::Error> for ClientError {
     fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() || err.is_connect() {
+            return Self::Unavailable {
+                message: format!("{}", err),
+            };
+        }
+
         Self::InternalError {
             message: format!("{}", err),
         }
@@ -63,24 +83,97 @@ impl fmt::Display for ClientError {
     }
 }
 
+/// Upper bound of configurable retries
+pub const MAX_RETRIES: u32 = 10;
+/// Delay between two retries never exceed this one
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Timeouts and retries of api requests
+#[derive(Debug, Clone)]
+pub struct RequestPolicy {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    /// Maximum retries of a failed request (if error is retryable)
+    pub retries: u32,
+    /// Delay before first retry, doubled at each next retry
+    pub retry_backoff: Duration,
+}
+
+impl RequestPolicy {
+    /// Delay before given retry (0 is the first one), capped to MAX_RETRY_BACKOFF
+    pub fn backoff(&self, retry: u32) -> Duration {
+        2_u32
+            .checked_pow(retry)
+            .and_then(|factor| self.retry_backoff.checked_mul(factor))
+            .unwrap_or(MAX_RETRY_BACKOFF)
+            .min(MAX_RETRY_BACKOFF)
+    }
+
+    /// Call given request until it succeed, fail with a non retryable error or exhaust retries
+    pub fn retry<T, F>(&self, what: &str, mut request: F) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Result<T, ClientError>,
+    {
+        let mut retry = 0;
+        loop {
+            match request() {
+                Err(err) if err.is_retryable() && retry < self.retries => {
+                    let backoff = self.backoff(retry);
+                    retry += 1;
+                    log::warn!(
+                        "Request {} failed ({}), retry {}/{} in {} ms",
+                        what,
+                        err,
+                        retry,
+                        self.retries,
+                        backoff.as_millis()
+                    );
+                    thread::sleep(backoff);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Client {
     server_ip: String,
     server_port: u16,
     secure: bool,
-    disable_auth_token: String,
+    auth: Auth,
+    policy: RequestPolicy,
+    /// Seed of traits generated for received animated corpses
+    traits_seed: u64,
     client: reqwest::blocking::Client,
 }
 
 impl Client {
-    pub fn new(server_ip: &str, server_port: u16, secure: bool, disable_auth_token: String) -> Self {
-        Self {
+    pub fn new(
+        server_ip: &str,
+        server_port: u16,
+        secure: bool,
+        auth: Auth,
+        ca_certificate: Option<&[u8]>,
+        policy: RequestPolicy,
+        traits_seed: u64,
+    ) -> Result<Self, ClientError> {
+        let mut client = reqwest::blocking::Client::builder()
+            .connect_timeout(policy.connect_timeout)
+            .timeout(policy.timeout);
+        if let Some(ca_certificate) = ca_certificate {
+            client = client.add_root_certificate(reqwest::Certificate::from_pem(ca_certificate)?);
+        }
+
+        Ok(Self {
             server_ip: String::from(server_ip),
             server_port,
             secure,
-            disable_auth_token,
-            client: reqwest::blocking::Client::new(),
-        }
+            auth,
+            policy,
+            traits_seed,
+            client: client.build()?,
+        })
     }
 
     fn get_base_path(&self) -> String {
@@ -88,6 +181,24 @@ impl Client {
         return format!("{}://{}:{}", protocol, self.server_ip, self.server_port);
     }
 
+    /// Prepare a request authenticated according to client auth
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self.client.request(method, url);
+        for (name, value) in self.auth.headers() {
+            request = request.header(name, value);
+        }
+        request
+    }
+
+    /// Send an authenticated GET request, retrying it (with backoff) on retryable errors
+    fn get(&self, url: &str) -> Result<Response, ClientError> {
+        self.policy
+            .retry(url, || match self.request(Method::GET, url).send() {
+                Ok(response) => self.check_response(response),
+                Err(err) => Err(ClientError::from(err)),
+            })
+    }
+
     fn check_response(&self, response: Response) -> Result<Response, ClientError> {
         if response.status().as_u16() == 404 {
             return Err(ClientError::NotFound {
@@ -96,22 +207,29 @@ impl Client {
         }
 
         if response.status().is_client_error() {
-            let error: ErrorResponse = response.json()?;
             return Err(ClientError::ClientSideError {
-                message: error.message,
+                message: self.error_message(response),
             });
         }
 
         if !response.status().is_success() {
-            let error: ErrorResponse = response.json()?;
             return Err(ClientError::ServerSideError {
-                message: error.message,
+                message: self.error_message(response),
             });
         }
 
         Ok(response)
     }
 
+    /// Message of error response, or its status if it isn't an api error (like from a proxy)
+    fn error_message(&self, response: Response) -> String {
+        let status = response.status();
+        match response.json::<ErrorResponse>() {
+            Ok(error) => error.message,
+            Err(_) => status.to_string(),
+        }
+    }
+
     pub fn get_animated_corpses(
         &self,
         world_row_i: u32,
@@ -123,12 +241,12 @@ impl Client {
             world_row_i,
             world_col_i
         );
-        let response: Response = self.check_response(self.client.get(url.as_str()).send()?)?;
+        let response: Response = self.get(url.as_str())?;
 
         let value = response.json::<Value>()?;
         let mut animated_corpses: Vec<Box<dyn AnimatedCorpse + Send + Sync>> = vec![];
         for item in value.as_array().expect("No array found in response").iter() {
-            match animated_corpse_from_value(item.clone()) {
+            match animated_corpse_from_value(item.clone(), self.traits_seed) {
                 Ok(animated_corpse) => {
                     animated_corpses.push(animated_corpse);
                 }
@@ -149,10 +267,10 @@ impl Client {
             self.get_base_path(),
             animated_corpse_id,
         );
-        let response: Response = self.check_response(self.client.get(url.as_str()).send()?)?;
+        let response: Response = self.get(url.as_str())?;
 
         let value = response.json::<Value>()?;
-        match animated_corpse_from_value(value) {
+        match animated_corpse_from_value(value, self.traits_seed) {
             Ok(animated_corpse) => {
                 Ok(animated_corpse)
             }
@@ -173,15 +291,7 @@ impl Client {
             world_row_i,
             world_col_i
         );
-        let response: Response = self.check_response(
-            self.client.request(
-                Method::GET,
-                url.as_str(),
-            ).header(
-                HEADER_NAME__DISABLE_AUTH_TOKEN,
-                &self.disable_auth_token
-            ).send()?
-        )?;
+        let response: Response = self.get(url.as_str())?;
 
         Ok(response.json::<Vec<model::Character>>()?)
     }
@@ -197,21 +307,13 @@ impl Client {
             world_row_i,
             world_col_i
         );
-        let response: Response = self.check_response(
-            self.client.request(
-                Method::GET,
-                url.as_str(),
-            ).header(
-                HEADER_NAME__DISABLE_AUTH_TOKEN,
-                &self.disable_auth_token
-            ).send()?
-        )?;
+        let response: Response = self.get(url.as_str())?;
         Ok(response.json::<Vec<model::Build>>()?)
     }
 
     pub fn get_world_source(&self) -> Result<String, ClientError> {
         let url = format!("{}/world/source", self.get_base_path(),);
-        let response: Response = self.check_response(self.client.get(url.as_str()).send()?)?;
+        let response: Response = self.get(url.as_str())?;
 
         Ok(response.text()?)
     }
@@ -227,15 +329,7 @@ impl Client {
             world_row_i,
             world_col_i
         );
-        let response: Response = self.check_response(
-            self.client.request(
-                Method::GET,
-                url.as_str(),
-            ).header(
-                HEADER_NAME__DISABLE_AUTH_TOKEN,
-                &self.disable_auth_token
-            ).send()?
-        )?;
+        let response: Response = self.get(url.as_str())?;
         let response_value = response.json::<Value>()?;
         match response_value["raw_source"].as_str() {
             None => {
@@ -249,8 +343,98 @@ impl Client {
 
     pub fn get_tiles_data(&self) -> Result<Value, ClientError> {
         let url = format!("{}/zones/tiles", self.get_base_path());
-        let response: Response = self.check_response(self.client.get(url.as_str()).send()?)?;
+        let response: Response = self.get(url.as_str())?;
 
         Ok(response.json::<Value>()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(retry_backoff: Duration) -> RequestPolicy {
+        RequestPolicy {
+            connect_timeout: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+            retries: MAX_RETRIES,
+            retry_backoff,
+        }
+    }
+
+    #[test]
+    fn backoff_is_doubled_at_each_retry() {
+        let policy = policy(Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(3), Duration::from_millis(4000));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(
+            policy(Duration::from_millis(500)).backoff(10),
+            MAX_RETRY_BACKOFF
+        );
+        assert_eq!(
+            policy(Duration::from_millis(500)).backoff(40),
+            MAX_RETRY_BACKOFF
+        );
+        assert_eq!(policy(Duration::MAX).backoff(1), MAX_RETRY_BACKOFF);
+    }
+
+    /// Count calls of a request always failing with given error
+    fn attempts(error: impl Fn() -> ClientError) -> u32 {
+        let mut attempts = 0;
+        let result: Result<(), ClientError> = policy(Duration::ZERO).retry("test", || {
+            attempts += 1;
+            Err(error())
+        });
+        assert!(result.is_err());
+        attempts
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let message = || "message".to_string();
+        assert_eq!(attempts(|| ClientError::NotFound { message: message() }), 1);
+        assert_eq!(
+            attempts(|| ClientError::ClientSideError { message: message() }),
+            1
+        );
+        assert_eq!(
+            attempts(|| ClientError::InternalError { message: message() }),
+            1
+        );
+    }
+
+    #[test]
+    fn transient_errors_are_retried_until_exhausted() {
+        let message = || "message".to_string();
+        assert_eq!(
+            attempts(|| ClientError::ServerSideError { message: message() }),
+            MAX_RETRIES + 1
+        );
+        assert_eq!(
+            attempts(|| ClientError::Unavailable { message: message() }),
+            MAX_RETRIES + 1
+        );
+    }
+
+    #[test]
+    fn retry_stops_on_success() {
+        let mut attempts = 0;
+        let result = policy(Duration::ZERO).retry("test", || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(ClientError::Unavailable {
+                    message: "message".to_string(),
+                })
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+/// Night is from NIGHT_START_HOUR to DAY_START_HOUR
+pub const DAY_START_HOUR: u32 = 6;
+pub const NIGHT_START_HOUR: u32 = 20;
+
+/// Game day/night cycle. Game days are aligned on unix epoch, so every rollac instance
+/// (and server, if it use same day length) agree on game time.
+#[derive(Debug, Clone, Copy)]
+pub struct GameClock {
+    day_length: Duration,
+}
+
+impl GameClock {
+    pub fn new(day_length: Duration) -> Self {
+        Self { day_length }
+    }
+
+    pub fn now(&self) -> GameTime {
+        self.at(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Game time at given real time since unix epoch
+    pub fn at(&self, since_epoch: Duration) -> GameTime {
+        let day_length_ms = self.day_length.as_millis().max(1);
+        let in_day_ms = since_epoch.as_millis() % day_length_ms;
+        GameTime {
+            minute_of_day: (in_day_ms * MINUTES_PER_DAY as u128 / day_length_ms) as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameTime {
+    /// From 0 (midnight) to MINUTES_PER_DAY excluded
+    pub minute_of_day: u32,
+}
+
+impl GameTime {
+    pub fn hour(&self) -> u32 {
+        self.minute_of_day / 60
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.hour() >= NIGHT_START_HOUR || self.hour() < DAY_START_HOUR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_follow_day_cycle() {
+        let clock = GameClock::new(Duration::from_secs(24 * 60));
+
+        assert_eq!(clock.at(Duration::from_secs(0)).minute_of_day, 0);
+        assert_eq!(clock.at(Duration::from_secs(6 * 60)).hour(), 6);
+        assert_eq!(
+            clock.at(Duration::from_secs(24 * 60 - 1)).minute_of_day,
+            1439
+        );
+        // Next day
+        assert_eq!(
+            clock.at(Duration::from_secs(24 * 60 + 90)).minute_of_day,
+            90
+        );
+    }
+
+    #[test]
+    fn at_with_zero_day_length() {
+        let clock = GameClock::new(Duration::from_secs(0));
+
+        assert_eq!(clock.at(Duration::from_secs(12345)).minute_of_day, 0);
+    }
+
+    #[test]
+    fn night_hours() {
+        let night_at = |hour: u32| {
+            GameTime {
+                minute_of_day: hour * 60,
+            }
+            .is_night()
+        };
+
+        assert!(night_at(0));
+        assert!(night_at(DAY_START_HOUR - 1));
+        assert!(!night_at(DAY_START_HOUR));
+        assert!(!night_at(NIGHT_START_HOUR - 1));
+        assert!(night_at(NIGHT_START_HOUR));
+        assert!(night_at(23));
+    }
+}
@@ -0,0 +1,524 @@
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::ac::traits::DEFAULT_TRAITS_SEED;
+use crate::auth::{redacted, Auth, Credentials};
+use crate::client::{RequestPolicy, MAX_RETRIES};
+use crate::error;
+use crate::message::ZoneCoordinates;
+use crate::scheduler::OverrunPolicy;
+use crate::tile::ParseMode;
+use crate::util;
+
+pub const DEFAULT_CONFIG_FILE: &str = "rollac.toml";
+pub const ENV_PREFIX: &str = "ROLLAC_";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConnectionConfig {
+    pub host: String,
+    pub port: u16,
+    /// Use https and wss (with server certificate verification)
+    pub secure: bool,
+    /// PEM CA certificate(s) to trust in addition of system ones (like self-signed server)
+    pub ca_file: Option<PathBuf>,
+    /// Secrets (tokens and password) are never serialized, so never printed
+    #[serde(skip_serializing)]
+    pub disable_auth_token: Option<String>,
+    /// Service account token, sent as bearer
+    #[serde(skip_serializing)]
+    pub auth_token: Option<String>,
+    /// Service account login, sent with password as basic auth
+    pub username: Option<String>,
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for ConnectionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("secure", &self.secure)
+            .field("ca_file", &self.ca_file)
+            .field("disable_auth_token", &redacted(&self.disable_auth_token))
+            .field("auth_token", &redacted(&self.auth_token))
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .finish()
+    }
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 5000,
+            secure: false,
+            ca_file: None,
+            disable_auth_token: None,
+            auth_token: None,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub connect_timeout_ms: u64,
+    /// Whole request timeout
+    pub timeout_ms: u64,
+    /// Maximum retries of a request which failed with a server side or availability error
+    pub retries: u32,
+    /// Delay before first retry, doubled at each next retry
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: 5000,
+            timeout_ms: 30000,
+            retries: 3,
+            retry_backoff_ms: 500,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TickConfig {
+    /// Interval between two animations of a zone
+    pub each_ms: u64,
+    pub overrun: OverrunPolicy,
+    /// Maximum random offset of each zone tick (default is tick interval)
+    pub jitter_ms: Option<u64>,
+}
+
+impl Default for TickConfig {
+    fn default() -> Self {
+        Self {
+            each_ms: 5000,
+            overrun: OverrunPolicy::Skip,
+            jitter_ms: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// Real duration of a game day (day and night)
+    pub day_length_ms: u64,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            day_length_ms: 3_600_000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ZonesConfig {
+    /// Load zones when a message or event is addressed to them instead of at startup
+    pub lazy: bool,
+    /// Handle only these zones ("ROW.COL"), all zones if empty
+    pub only: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LogConfig {
+    /// env_logger filter, overridden by RUST_LOG
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: "rollac=info".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BehaviorConfig {
+    /// Forget not acknowledged move requests after this delay
+    pub pending_move_timeout_ms: u64,
+    /// Seed of animated corpses traits generation. Traits are not stored, keep this seed to
+    /// keep them across restarts
+    pub traits_seed: u64,
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            pending_move_timeout_ms: 15000,
+            traits_seed: DEFAULT_TRAITS_SEED,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SourceConfig {
+    /// "lenient" replace unknown world and zone chars by default tile with a warning,
+    /// "strict" fail on them
+    pub mode: ParseMode,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::Lenient,
+        }
+    }
+}
+
+/// Daemon configuration. Built from defaults, then config file, then ROLLAC_* environment
+/// variables, then command line arguments.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    pub connection: ConnectionConfig,
+    pub http: HttpConfig,
+    pub tick: TickConfig,
+    pub clock: ClockConfig,
+    pub zones: ZonesConfig,
+    pub source: SourceConfig,
+    pub log: LogConfig,
+    pub behavior: BehaviorConfig,
+}
+
+fn env_var<T>(name: &str) -> Result<Option<T>, error::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let name = format!("{}{}", ENV_PREFIX, name);
+    match env::var(&name) {
+        Ok(value) => value.parse().map(Some).map_err(|err| {
+            error::Error::config(
+                format!("Invalid value '{}' for {}: {}", value, name, err),
+                None,
+            )
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn env_bool(name: &str) -> Result<Option<bool>, error::Error> {
+    match env_var::<String>(name)? {
+        Some(value) => match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Some(true)),
+            "0" | "false" | "no" | "off" => Ok(Some(false)),
+            _ => Err(error::Error::config(
+                format!("Invalid boolean '{}' for {}{}", value, ENV_PREFIX, name),
+                None,
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+impl Config {
+    /// Read config file at given path or, if None, at ROLLAC_CONFIG or DEFAULT_CONFIG_FILE
+    /// (if it exists), then apply environment variables
+    pub fn load(path: Option<&Path>) -> Result<Self, error::Error> {
+        let path: Option<PathBuf> = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => match env_var::<String>("CONFIG")? {
+                Some(path) => Some(PathBuf::from(path)),
+                None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+            },
+        };
+
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, error::Error> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            error::Error::config(
+                format!("Unable to read config file {}: {}", path.display(), err),
+                Some(Box::new(err)),
+            )
+        })?;
+        toml::from_str(&content).map_err(|err| {
+            error::Error::config(
+                format!("Unable to parse config file {}: {}", path.display(), err),
+                Some(Box::new(err)),
+            )
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), error::Error> {
+        if let Some(host) = env_var("HOST")? {
+            self.connection.host = host;
+        }
+        if let Some(port) = env_var("PORT")? {
+            self.connection.port = port;
+        }
+        if let Some(secure) = env_bool("SECURE")? {
+            self.connection.secure = secure;
+        }
+        if let Some(ca_file) = env_var::<String>("CA_FILE")? {
+            self.connection.ca_file = Some(PathBuf::from(ca_file));
+        }
+        if let Some(disable_auth_token) = env_var("DISABLE_AUTH_TOKEN")? {
+            self.connection.disable_auth_token = Some(disable_auth_token);
+        }
+        if let Some(auth_token) = env_var("AUTH_TOKEN")? {
+            self.connection.auth_token = Some(auth_token);
+        }
+        if let Some(username) = env_var("USERNAME")? {
+            self.connection.username = Some(username);
+        }
+        if let Some(password) = env_var("PASSWORD")? {
+            self.connection.password = Some(password);
+        }
+        if let Some(connect_timeout_ms) = env_var("HTTP_CONNECT_TIMEOUT_MS")? {
+            self.http.connect_timeout_ms = connect_timeout_ms;
+        }
+        if let Some(timeout_ms) = env_var("HTTP_TIMEOUT_MS")? {
+            self.http.timeout_ms = timeout_ms;
+        }
+        if let Some(retries) = env_var("HTTP_RETRIES")? {
+            self.http.retries = retries;
+        }
+        if let Some(retry_backoff_ms) = env_var("HTTP_RETRY_BACKOFF_MS")? {
+            self.http.retry_backoff_ms = retry_backoff_ms;
+        }
+        if let Some(each_ms) = env_var("TICK_EACH_MS")? {
+            self.tick.each_ms = each_ms;
+        }
+        if let Some(overrun) = env_var("TICK_OVERRUN")? {
+            self.tick.overrun = overrun;
+        }
+        if let Some(jitter_ms) = env_var("TICK_JITTER_MS")? {
+            self.tick.jitter_ms = Some(jitter_ms);
+        }
+        if let Some(day_length_ms) = env_var("CLOCK_DAY_LENGTH_MS")? {
+            self.clock.day_length_ms = day_length_ms;
+        }
+        if let Some(lazy) = env_bool("ZONES_LAZY")? {
+            self.zones.lazy = lazy;
+        }
+        if let Some(only) = env_var::<String>("ZONES_ONLY")? {
+            self.zones.only = only
+                .split(',')
+                .map(|zone| zone.trim().to_string())
+                .filter(|zone| !zone.is_empty())
+                .collect();
+        }
+        if let Some(mode) = env_var("SOURCE_MODE")? {
+            self.source.mode = mode;
+        }
+        if let Some(filter) = env_var("LOG")? {
+            self.log.filter = filter;
+        }
+        if let Some(pending_move_timeout_ms) = env_var("PENDING_MOVE_TIMEOUT_MS")? {
+            self.behavior.pending_move_timeout_ms = pending_move_timeout_ms;
+        }
+        if let Some(traits_seed) = env_var("TRAITS_SEED")? {
+            self.behavior.traits_seed = traits_seed;
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), error::Error> {
+        let mut errors: Vec<String> = vec![];
+
+        if self.connection.host.trim().is_empty() {
+            errors.push("connection.host must not be empty".to_string());
+        }
+        if self.connection.port == 0 {
+            errors.push("connection.port must not be 0".to_string());
+        }
+        if let Some(ca_file) = &self.connection.ca_file {
+            if !self.connection.secure {
+                errors.push("connection.ca_file is set but connection is not secure".to_string());
+            }
+            if !ca_file.is_file() {
+                errors.push(format!(
+                    "connection.ca_file {} is not a file",
+                    ca_file.display()
+                ));
+            }
+        }
+        match (
+            &self.connection.auth_token,
+            &self.connection.username,
+            &self.connection.password,
+        ) {
+            (Some(_), Some(_), _) => errors.push(
+                "connection.auth_token and connection.username are mutually exclusive".to_string(),
+            ),
+            (_, Some(_), None) => errors.push("connection.username require password".to_string()),
+            (_, None, Some(_)) => errors.push("connection.password require username".to_string()),
+            _ => {}
+        }
+        if self.http.connect_timeout_ms == 0 {
+            errors.push("http.connect_timeout_ms must be greater than 0".to_string());
+        }
+        if self.http.timeout_ms == 0 {
+            errors.push("http.timeout_ms must be greater than 0".to_string());
+        }
+        if self.http.retries > MAX_RETRIES {
+            errors.push(format!("http.retries must not be greater than {}", MAX_RETRIES));
+        }
+        if self.tick.each_ms == 0 {
+            errors.push("tick.each_ms must be greater than 0".to_string());
+        }
+        if self.clock.day_length_ms == 0 {
+            errors.push("clock.day_length_ms must be greater than 0".to_string());
+        }
+        if self.behavior.pending_move_timeout_ms == 0 {
+            errors.push("behavior.pending_move_timeout_ms must be greater than 0".to_string());
+        }
+        for zone in &self.zones.only {
+            if let Err(err) = util::parse_zone_coordinates(zone) {
+                errors.push(format!("zones.only: {}", err));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(error::Error::config(
+                errors.join(", "),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Zones to handle, empty if all zones must be handled
+    pub fn only_zones(&self) -> Vec<ZoneCoordinates> {
+        self.zones
+            .only
+            .iter()
+            .filter_map(|zone| util::parse_zone_coordinates(zone).ok())
+            .collect()
+    }
+
+    pub fn auth(&self) -> Auth {
+        let credentials = match (
+            &self.connection.auth_token,
+            &self.connection.username,
+            &self.connection.password,
+        ) {
+            (Some(token), _, _) => Some(Credentials::Token(token.clone())),
+            (None, Some(username), Some(password)) => Some(Credentials::Basic {
+                username: username.clone(),
+                password: password.clone(),
+            }),
+            _ => None,
+        };
+        Auth::new(self.connection.disable_auth_token.clone(), credentials)
+    }
+
+    pub fn request_policy(&self) -> RequestPolicy {
+        RequestPolicy {
+            connect_timeout: Duration::from_millis(self.http.connect_timeout_ms),
+            timeout: Duration::from_millis(self.http.timeout_ms),
+            retries: self.http.retries,
+            retry_backoff: Duration::from_millis(self.http.retry_backoff_ms),
+        }
+    }
+
+    /// Content of connection.ca_file, if any
+    pub fn ca_certificate(&self) -> Result<Option<Vec<u8>>, error::Error> {
+        match &self.connection.ca_file {
+            Some(ca_file) => fs::read(ca_file).map(Some).map_err(|err| {
+                error::Error::config(
+                    format!("Unable to read CA file {}: {}", ca_file.display(), err),
+                    Some(Box::new(err)),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, error::Error> {
+        toml::to_string_pretty(self)
+            .map_err(|err| {
+                error::Error::config(
+                    format!("Unable to serialize config: {}", err),
+                    Some(Box::new(err)),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_then_file_then_env() {
+        let path = env::temp_dir().join(format!("rollac-test-{}.toml", std::process::id()));
+        fs::write(&path, "[connection]\nport = 6000\n\n[tick]\neach_ms = 1000\n").unwrap();
+
+        // Only test using environment, to not race with others
+        env::set_var("ROLLAC_TICK_EACH_MS", "2000");
+        let config = Config::load(Some(&path));
+        env::set_var("ROLLAC_TICK_EACH_MS", "often");
+        let invalid = Config::load(Some(&path));
+        env::remove_var("ROLLAC_TICK_EACH_MS");
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.connection.host, "127.0.0.1");
+        assert_eq!(config.connection.port, 6000);
+        assert_eq!(config.tick.each_ms, 2000);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn secrets_are_not_serialized_nor_debugged() {
+        let mut config = Config::default();
+        config.connection.disable_auth_token = Some("disable-secret".to_string());
+        config.connection.auth_token = Some("token-secret".to_string());
+        config.connection.username = Some("rollac".to_string());
+        config.connection.password = Some("password-secret".to_string());
+
+        let toml = config.to_toml().unwrap();
+        assert!(!toml.contains("secret"));
+        assert!(toml.contains("rollac"));
+
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("rollac"));
+    }
+
+    #[test]
+    fn validate_collect_errors() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        config.connection.port = 0;
+        config.http.retries = MAX_RETRIES + 1;
+        config.tick.each_ms = 0;
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("connection.port"));
+        assert!(message.contains("http.retries"));
+        assert!(message.contains("tick.each_ms"));
+    }
+}
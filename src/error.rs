@@ -1,52 +1,237 @@
 use crate::client::ClientError;
+use crate::source::SourceError;
 use std::time::SystemTimeError;
-use std::{fmt, io};
+use std::{error, fmt, io};
 
-#[derive(Debug, Clone)]
-pub struct Error {
-    message: String,
+pub type BoxedError = Box<dyn error::Error + Send + Sync>;
+
+/// Exit codes, following sysexits.h
+pub const EXIT_USAGE: i32 = 64;
+pub const EXIT_DATA: i32 = 65;
+pub const EXIT_UNAVAILABLE: i32 = 69;
+pub const EXIT_SOFTWARE: i32 = 70;
+pub const EXIT_IO: i32 = 74;
+pub const EXIT_CONFIG: i32 = 78;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Api request failed
+    Client(ClientError),
+    /// Websocket connection failed
+    Socket {
+        message: String,
+        source: Option<BoxedError>,
+    },
+    /// Received data (like an event) can't be decoded
+    Parse {
+        message: String,
+        source: Option<BoxedError>,
+    },
+    /// World or zone source (or tiles description) is invalid
+    Source {
+        message: String,
+        /// Positioned source error, if any
+        source: Option<SourceError>,
+    },
+    /// Configuration (file, environment or command line) is invalid
+    Config {
+        message: String,
+        source: Option<BoxedError>,
+    },
+    /// Command can't be executed with given arguments
+    Usage { message: String },
+    Io(io::Error),
+    SystemTime(SystemTimeError),
 }
 
 impl Error {
-    pub fn new(message: String) -> Self {
-        Self { message }
+    pub fn socket(message: String, source: Option<BoxedError>) -> Self {
+        Self::Socket { message, source }
+    }
+
+    pub fn parse(message: String, source: Option<BoxedError>) -> Self {
+        Self::Parse { message, source }
+    }
+
+    pub fn invalid_source(message: String, source: Option<SourceError>) -> Self {
+        Self::Source { message, source }
+    }
+
+    pub fn config(message: String, source: Option<BoxedError>) -> Self {
+        Self::Config { message, source }
+    }
+
+    pub fn usage(message: String) -> Self {
+        Self::Usage { message }
+    }
+
+    /// Process exit code to use when this error stop the program
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Client(ClientError::NotFound { .. }) => EXIT_DATA,
+            Error::Client(_) | Error::Socket { .. } => EXIT_UNAVAILABLE,
+            Error::Parse { .. } | Error::Source { .. } => EXIT_DATA,
+            Error::Config { .. } => EXIT_CONFIG,
+            Error::Usage { .. } => EXIT_USAGE,
+            Error::Io(_) => EXIT_IO,
+            Error::SystemTime(_) => EXIT_SOFTWARE,
+        }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            Error::Client(err) => write!(f, "client error: {}", err),
+            Error::Socket { message, .. } => write!(f, "socket error: {}", message),
+            Error::Parse { message, .. } => write!(f, "parse error: {}", message),
+            Error::Source { message, .. } => write!(f, "source error: {}", message),
+            Error::Config { message, .. } => write!(f, "config error: {}", message),
+            Error::Usage { message } => write!(f, "{}", message),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::SystemTime(err) => write!(f, "system time error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Client(err) => Some(err),
+            Error::Socket { source, .. }
+            | Error::Parse { source, .. }
+            | Error::Config { source, .. } => source
+                .as_ref()
+                .map(|source| source.as_ref() as &(dyn error::Error + 'static)),
+            Error::Source { source, .. } => source
+                .as_ref()
+                .map(|source| source as &(dyn error::Error + 'static)),
+            Error::Usage { .. } => None,
+            Error::Io(err) => Some(err),
+            Error::SystemTime(err) => Some(err),
+        }
     }
 }
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        Self {
-            message: format!("io error: {}", err),
-        }
+        Self::Io(err)
     }
 }
 
 impl From<SystemTimeError> for Error {
     fn from(err: SystemTimeError) -> Self {
-        Self {
-            message: format!("system time error: {}", err),
-        }
+        Self::SystemTime(err)
     }
 }
 
 impl From<ClientError> for Error {
     fn from(err: ClientError) -> Self {
-        Self {
-            message: format!("client error: {}", err),
-        }
+        Self::Client(err)
+    }
+}
+
+impl From<SourceError> for Error {
+    fn from(err: SourceError) -> Self {
+        Self::invalid_source(format!("{}", err), Some(err))
     }
 }
 
 impl From<serde_json::Error> for Error {
     fn from(err: serde_json::Error) -> Self {
-        Self {
-            message: format!("serde json error: {}", err),
+        Self::parse(format!("serde json error: {}", err), Some(Box::new(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Position;
+    use std::error::Error as _;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn client(err: ClientError) -> Error {
+        Error::from(err)
+    }
+
+    fn boxed(message: &str) -> Option<BoxedError> {
+        Some(Box::new(io::Error::other(message)))
+    }
+
+    #[test]
+    fn exit_codes() {
+        let message = || "message".to_string();
+        let system_time = UNIX_EPOCH
+            .duration_since(UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap_err();
+        let cases = vec![
+            (
+                client(ClientError::NotFound { message: message() }),
+                EXIT_DATA,
+            ),
+            (
+                client(ClientError::ClientSideError { message: message() }),
+                EXIT_UNAVAILABLE,
+            ),
+            (
+                client(ClientError::ServerSideError { message: message() }),
+                EXIT_UNAVAILABLE,
+            ),
+            (
+                client(ClientError::Unavailable { message: message() }),
+                EXIT_UNAVAILABLE,
+            ),
+            (
+                client(ClientError::InternalError { message: message() }),
+                EXIT_UNAVAILABLE,
+            ),
+            (Error::socket(message(), None), EXIT_UNAVAILABLE),
+            (Error::parse(message(), None), EXIT_DATA),
+            (Error::invalid_source(message(), None), EXIT_DATA),
+            (Error::config(message(), None), EXIT_CONFIG),
+            (Error::usage(message()), EXIT_USAGE),
+            (
+                Error::from(io::Error::other("io")),
+                EXIT_IO,
+            ),
+            (Error::from(system_time), EXIT_SOFTWARE),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code, "{:?}", error);
         }
     }
+
+    #[test]
+    fn sources_are_wrapped_errors() {
+        let error = client(ClientError::NotFound {
+            message: "zone".to_string(),
+        });
+        let source = error.source().unwrap();
+        assert_eq!(source.to_string(), "Not found: zone");
+        assert!(source.downcast_ref::<ClientError>().is_some());
+
+        for error in [
+            Error::socket("socket".to_string(), boxed("wrapped")),
+            Error::config("config".to_string(), boxed("wrapped")),
+            Error::from(io::Error::other("wrapped")),
+        ] {
+            let source = error.source().unwrap();
+            assert_eq!(source.to_string(), "wrapped");
+            assert!(source.downcast_ref::<io::Error>().is_some());
+        }
+
+        let error = Error::from(SourceError::new(
+            Position { line: 2, column: 3 },
+            "wrapped".to_string(),
+        ));
+        assert!(error
+            .source()
+            .unwrap()
+            .downcast_ref::<SourceError>()
+            .is_some());
+
+        assert!(Error::socket("socket".to_string(), None).source().is_none());
+        assert!(Error::config("config".to_string(), None).source().is_none());
+        assert!(Error::usage("usage".to_string()).source().is_none());
+    }
 }
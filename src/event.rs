@@ -2,17 +2,21 @@ use std::collections::HashMap;
 
 use async_std::channel::Sender;
 use async_std::sync::Mutex;
+use async_std::task;
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::client::Client;
 use crate::error::Error;
 use crate::message::{Message, SendEventMessage, ZoneMessage};
-use crate::zone::Zone;
+use crate::registry;
+use crate::registry::Zones;
 use crate::{model, socket};
 
 pub const PLAYER_MOVE: &str = "PLAYER_MOVE";
 pub const ANIMATED_CORPSE_MOVE: &str = "ANIMATED_CORPSE_MOVE";
+pub const ANIMATED_CORPSE_EAT: &str = "ANIMATED_CORPSE_EAT";
 pub const CLIENT_WANT_CLOSE: &str = "CLIENT_WANT_CLOSE";
 pub const SERVER_PERMIT_CLOSE: &str = "SERVER_PERMIT_CLOSE";
 pub const CHARACTER_ENTER_ZONE: &str = "CHARACTER_ENTER_ZONE";
@@ -35,6 +39,11 @@ pub enum ZoneEventType {
         to_col_i: u32,
         animated_corpse_id: u32,
     },
+    AnimatedCorpseEat {
+        zone_row_i: u32,
+        zone_col_i: u32,
+        animated_corpse_id: u32,
+    },
     CharacterEnter {
         zone_row_i: u32,
         zone_col_i: u32,
@@ -91,6 +100,17 @@ impl ZoneEvent {
                         as u32,
                 },
             }),
+            &ANIMATED_CORPSE_EAT => Ok(ZoneEvent {
+                world_row_i,
+                world_col_i,
+                event_type_name: String::from(ANIMATED_CORPSE_EAT),
+                event_type: ZoneEventType::AnimatedCorpseEat {
+                    zone_row_i: data["zone_row_i"].as_i64().expect(DE_ERR_MSG) as u32,
+                    zone_col_i: data["zone_col_i"].as_i64().expect(DE_ERR_MSG) as u32,
+                    animated_corpse_id: data["animated_corpse_id"].as_i64().expect(DE_ERR_MSG)
+                        as u32,
+                },
+            }),
             &CLIENT_WANT_CLOSE => Ok(ZoneEvent {
                 world_row_i,
                 world_col_i,
@@ -161,7 +181,7 @@ impl ZoneEvent {
                     animated_corpse_id: data["animated_corpse_id"].as_i64().expect(DE_ERR_MSG) as u32,
                 },
             }),
-            _ => Err(Error::new(format!("Unknown event {}", &type_))),
+            _ => Err(Error::parse(format!("Unknown event {}", &type_), None)),
         }
     }
 
@@ -181,6 +201,18 @@ impl ZoneEvent {
                 world_row_i,
                 world_col_i,
             },
+            SendEventMessage::AnimatedCorpseEat(animated_corpse_id, zone_row_i, zone_col_i) => {
+                Self {
+                    event_type_name: String::from(ANIMATED_CORPSE_EAT),
+                    event_type: ZoneEventType::AnimatedCorpseEat {
+                        zone_row_i,
+                        zone_col_i,
+                        animated_corpse_id,
+                    },
+                    world_row_i,
+                    world_col_i,
+                }
+            }
         }
     }
 }
@@ -200,7 +232,8 @@ impl Serialize for ZoneEvent {
 }
 
 pub async fn on_events(
-    zones: &Mutex<Vec<Zone>>,
+    zones: &Mutex<Zones>,
+    client: &Client,
     channel_sender: &Sender<Message>,
     socket: &socket::Channel,
 ) {
@@ -212,6 +245,8 @@ pub async fn on_events(
         match &event.event_type {
             // Ignore internal mechanisms events
             ZoneEventType::ClientWantClose | ZoneEventType::ServerPermitClose => continue,
+            // Hunger was already updated when eat was decided
+            ZoneEventType::AnimatedCorpseEat { .. } => {}
             // First convert some event to messages
             ZoneEventType::PlayerMove {
                 to_row_i,
@@ -261,18 +296,51 @@ pub async fn on_events(
                     (event.world_row_i, event.world_col_i),
                 ));
             }
-            ZoneEventType::NewAnimatedCorpse { animated_corpse_id} => {
-                messages.push(Message::Zone(
-                    ZoneMessage::AddAnimatedCorpse(*animated_corpse_id),
-                    (event.world_row_i, event.world_col_i),
-                ));
+            ZoneEventType::NewAnimatedCorpse { animated_corpse_id } => {
+                // Blocking api request, run it in a blocking task and without zones lock
+                let client = client.clone();
+                let animated_corpse_id = *animated_corpse_id;
+                match task::spawn_blocking(move || client.get_animated_corpse(animated_corpse_id))
+                    .await
+                {
+                    Ok(animated_corpse) => {
+                        // Animated corpse which changed zone leaves the previous one
+                        let zones = zones.lock().await;
+                        for coordinates in zones.coordinates() {
+                            if coordinates != (event.world_row_i, event.world_col_i)
+                                && zones
+                                    .get(coordinates)
+                                    .and_then(|zone| zone.animated_corpse(animated_corpse_id))
+                                    .is_some()
+                            {
+                                messages.push(Message::Zone(
+                                    ZoneMessage::RemoveAnimatedCorpse(animated_corpse_id),
+                                    coordinates,
+                                ));
+                            }
+                        }
+                        messages.push(Message::Zone(
+                            ZoneMessage::AddAnimatedCorpse(*animated_corpse.base()),
+                            (event.world_row_i, event.world_col_i),
+                        ))
+                    }
+                    Err(err) => log::error!(
+                        "Fail to get new animated corpse {}: {}",
+                        animated_corpse_id,
+                        err
+                    ),
+                }
             }
         }
 
-        for zone in zones.lock().await.iter_mut() {
-            if event.world_row_i == zone.world_row_i && event.world_col_i == zone.world_col_i {
-                messages.extend(zone.on_event(&event));
-            }
+        // Messages derived from event are still forwarded when zone is not loaded, so they
+        // are reported (and can trigger a lazy load) by messages loop
+        if let Some(zone) =
+            registry::lock_loaded(zones, (event.world_row_i, event.world_col_i), "event")
+                .await
+                .get_mut((event.world_row_i, event.world_col_i))
+        {
+            messages.extend(zone.on_event(&event))
         }
 
         for message in messages {
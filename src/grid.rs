@@ -0,0 +1,215 @@
+/// (row_i, col_i)
+pub type GridPosition = (u32, u32);
+
+/// 2D grid of cells. All rows have same width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    rows: Vec<Vec<T>>,
+}
+
+impl<T> Grid<T> {
+    /// Grid as wide as its longest row, shorter rows are padded with filler
+    pub fn new(mut rows: Vec<Vec<T>>, filler: T) -> Self
+    where
+        T: Clone,
+    {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, filler.clone());
+        }
+        Self { width, rows }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn get(&self, position: GridPosition) -> Option<&T> {
+        self.rows
+            .get(position.0 as usize)
+            .and_then(|row| row.get(position.1 as usize))
+    }
+
+    pub fn contains(&self, position: GridPosition) -> bool {
+        self.get(position).is_some()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.rows.iter().map(|row| row.as_slice())
+    }
+
+    /// All cells, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, &T)> {
+        self.rows.iter().enumerate().flat_map(|(row_i, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(col_i, cell)| ((row_i as u32, col_i as u32), cell))
+        })
+    }
+
+    /// Cells of the square of given radius around center (center included), row by row
+    pub fn region(
+        &self,
+        center: GridPosition,
+        radius: u32,
+    ) -> impl Iterator<Item = (GridPosition, &T)> {
+        let (center_row_i, center_col_i) = center;
+        let cols = center_col_i.saturating_sub(radius)..=center_col_i.saturating_add(radius);
+
+        (center_row_i.saturating_sub(radius)..=center_row_i.saturating_add(radius)).flat_map(
+            move |row_i| {
+                cols.clone().filter_map(move |col_i| {
+                    self.get((row_i, col_i)).map(|cell| ((row_i, col_i), cell))
+                })
+            },
+        )
+    }
+
+    /// The (up to) 8 cells around position
+    pub fn neighbours(&self, position: GridPosition) -> impl Iterator<Item = (GridPosition, &T)> {
+        self.region(position, 1)
+            .filter(move |(neighbour, _)| *neighbour != position)
+    }
+
+    /// Build a grid of same shape by converting each cell
+    pub fn try_map<U, E, F>(&self, mut convert: F) -> Result<Grid<U>, E>
+    where
+        F: FnMut(GridPosition, &T) -> Result<U, E>,
+    {
+        let mut rows = Vec::with_capacity(self.rows.len());
+        for (row_i, row) in self.rows.iter().enumerate() {
+            let mut cells = Vec::with_capacity(row.len());
+            for (col_i, cell) in row.iter().enumerate() {
+                cells.push(convert((row_i as u32, col_i as u32), cell)?);
+            }
+            rows.push(cells);
+        }
+        Ok(Grid {
+            width: self.width,
+            rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_pad_rows_to_width() {
+        let grid = Grid::new(vec![vec![1], vec![1, 2, 3], vec![]], 0);
+
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid.get((0, 2)), Some(&0));
+        assert_eq!(grid.get((2, 0)), Some(&0));
+        assert_eq!(grid.get((1, 3)), None);
+        assert!(grid.rows().all(|row| row.len() == 3));
+    }
+
+    /// 3x4 grid where each cell is (row_i, col_i)
+    fn grid() -> Grid<GridPosition> {
+        Grid::new(
+            (0..3)
+                .map(|row_i| (0..4).map(|col_i| (row_i, col_i)).collect())
+                .collect(),
+            (0, 0),
+        )
+    }
+
+    fn positions<'a>(
+        cells: impl Iterator<Item = (GridPosition, &'a GridPosition)>,
+    ) -> Vec<GridPosition> {
+        cells
+            .map(|(position, cell)| {
+                assert_eq!(position, *cell);
+                position
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get() {
+        let grid = grid();
+
+        assert_eq!(grid.get((0, 0)), Some(&(0, 0)));
+        assert_eq!(grid.get((2, 3)), Some(&(2, 3)));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, 4)), None);
+        assert_eq!(grid.get((u32::MAX, u32::MAX)), None);
+    }
+
+    #[test]
+    fn neighbours_at_corners() {
+        let grid = grid();
+
+        assert_eq!(
+            positions(grid.neighbours((0, 0))),
+            vec![(0, 1), (1, 0), (1, 1)]
+        );
+        assert_eq!(
+            positions(grid.neighbours((2, 3))),
+            vec![(1, 2), (1, 3), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn neighbours_at_edges() {
+        let grid = grid();
+
+        assert_eq!(
+            positions(grid.neighbours((0, 1))),
+            vec![(0, 0), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            positions(grid.neighbours((1, 3))),
+            vec![(0, 2), (0, 3), (1, 2), (2, 2), (2, 3)]
+        );
+        assert_eq!(positions(grid.neighbours((1, 1))).len(), 8);
+        assert!(positions(grid.neighbours((u32::MAX, u32::MAX))).is_empty());
+    }
+
+    #[test]
+    fn region_is_clipped_to_bounds() {
+        let grid = grid();
+
+        assert_eq!(
+            positions(grid.region((0, 0), 1)),
+            vec![(0, 0), (0, 1), (1, 0), (1, 1)]
+        );
+        assert_eq!(positions(grid.region((1, 1), 5)).len(), 12);
+        assert_eq!(positions(grid.region((2, 3), 0)), vec![(2, 3)]);
+    }
+
+    #[test]
+    fn try_map_keeps_shape() {
+        let grid = Grid::new(vec![vec![1], vec![1, 2]], 0);
+        let mapped: Result<Grid<i32>, ()> = grid.try_map(|_, cell| Ok(cell * 10));
+
+        assert_eq!(
+            mapped.unwrap(),
+            Grid::new(vec![vec![10, 0], vec![10, 20]], 0)
+        );
+    }
+
+    #[test]
+    fn try_map_propagate_error_with_position() {
+        let mut converted = vec![];
+        let mapped = grid().try_map(|position, _| {
+            if position == (1, 2) {
+                return Err(position);
+            }
+            converted.push(position);
+            Ok(())
+        });
+
+        assert_eq!(mapped, Err((1, 2)));
+        assert_eq!(converted.len(), 6);
+        assert_eq!(converted.last(), Some(&(1, 1)));
+    }
+}
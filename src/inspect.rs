@@ -0,0 +1,172 @@
+use crate::ac::AnimatedCorpse;
+use crate::behavior::get_behaviors_for;
+use crate::client::Client;
+use crate::tile::world::WorldTiles;
+use crate::tile::ParseMode;
+use crate::world::World;
+use crate::source::{BlockContent, Document};
+use crate::{error, viewer, world, zone};
+use std::fs;
+use std::path::Path;
+
+const UNKNOWN_GLYPH: char = '?';
+
+fn print_animated_corpses(animated_corpses: &[Box<dyn AnimatedCorpse + Send + Sync>]) {
+    for animated_corpse in animated_corpses {
+        let behavior_names: Vec<String> = get_behaviors_for(animated_corpse)
+            .iter()
+            .map(|behavior| behavior.name().to_string())
+            .collect();
+        println!(
+            "  {} {:?} at {}.{} ({})",
+            animated_corpse.id(),
+            animated_corpse.type_(),
+            animated_corpse.zone_row_i(),
+            animated_corpse.zone_col_i(),
+            behavior_names.join(", "),
+        );
+    }
+}
+
+
+/// Print world grid with legend chars, then legend
+pub fn world(client: &Client, mode: ParseMode) -> Result<(), error::Error> {
+    let world = world::new(client, mode)?;
+
+    println!("World {}x{}", world.grid.width(), world.grid.height());
+    for row in world.grid.rows() {
+        println!(
+            "{}",
+            row.iter()
+                .map(|tile_id| world.tiles.char(tile_id).unwrap_or(UNKNOWN_GLYPH))
+                .collect::<String>()
+        );
+    }
+
+    println!();
+    println!("Legend:");
+    for (char_, tile_id) in world.tiles.legend() {
+        if world.tiles.default.as_ref() == Some(&tile_id) {
+            println!("  {} {} (default)", char_, tile_id);
+        } else {
+            println!("  {} {}", char_, tile_id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print zone tiles, builds, characters and animated corpses
+pub fn zone(
+    client: &Client,
+    world_row_i: u32,
+    world_col_i: u32,
+    mode: ParseMode,
+) -> Result<(), error::Error> {
+    let world = world::new(client, mode)?;
+    if !world.grid.contains((world_row_i, world_col_i)) {
+        return Err(error::Error::usage(format!(
+            "Zone {}.{} is outside world",
+            world_row_i, world_col_i
+        )));
+    }
+    let zone = zone::load(&world, client, world_row_i, world_col_i, mode)?;
+
+    println!("{}", viewer::render(&zone));
+
+    println!("Builds:");
+    for build in &zone.builds {
+        println!(
+            "  {} {} at {}.{}",
+            build.id, build.build_id, build.row_i, build.col_i
+        );
+    }
+
+    println!("Characters:");
+    for character in &zone.characters {
+        println!(
+            "  {} at {}.{}",
+            character.id, character.zone_row_i, character.zone_col_i
+        );
+    }
+
+    println!("Animated corpses:");
+    print_animated_corpses(&zone.animated_corpses);
+
+    Ok(())
+}
+
+/// Print animated corpses of each zone with their type and behaviors
+pub fn animated_corpses(client: &Client, mode: ParseMode) -> Result<(), error::Error> {
+    let world = world::new(client, mode)?;
+    let mut total = 0;
+
+    for ((world_row_i, world_col_i), _) in world.grid.iter() {
+        let animated_corpses = client.get_animated_corpses(world_row_i, world_col_i)?;
+        if animated_corpses.is_empty() {
+            continue;
+        }
+
+        println!(
+            "Zone {}.{}: {} animated corpses",
+            world_row_i,
+            world_col_i,
+            animated_corpses.len()
+        );
+        print_animated_corpses(&animated_corpses);
+        total += animated_corpses.len();
+    }
+
+    println!("Total of animated corpses: {}", total);
+    Ok(())
+}
+
+/// Parse a local world or zone source file and print its blocks, or print it back normalized.
+/// World files (with a legend) grid chars are checked against legend according to mode.
+pub fn source(path: &Path, format: bool, mode: ParseMode) -> Result<(), error::Error> {
+    let content = fs::read_to_string(path)?;
+    let (document, source_warnings) = Document::parse(&content, mode).map_err(|err| {
+        error::Error::invalid_source(format!("{}: {}", path.display(), err), Some(err))
+    })?;
+
+    if format {
+        print!("{}", document);
+        return Ok(());
+    }
+
+    for block in &document.blocks {
+        let description = match &block.content {
+            BlockContent::Meta(entries) => format!("{} entries", entries.len()),
+            BlockContent::Legend(legend) => format!("{} tiles", legend.entries.len()),
+            BlockContent::Geo(geo) => format!("{}x{}", geo.width(), geo.height()),
+            BlockContent::Raw(lines) => format!("{} lines", lines.len()),
+        };
+        println!("{} (line {}): {}", block.name, block.line, description);
+    }
+
+    if let Some(meta) = document.meta() {
+        println!("Meta:");
+        for entry in meta {
+            println!("  {}: {}", entry.key, entry.value);
+        }
+    }
+
+    let mut warnings = source_warnings;
+    if let (Some(legend), Some(geo)) = (document.legend(), document.geo()) {
+        let (_, world_warnings) =
+            World::new(geo, WorldTiles::new(legend), mode).map_err(|err| match err {
+                error::Error::Source { message, source } => {
+                    error::Error::invalid_source(format!("{}: {}", path.display(), message), source)
+                }
+                err => err,
+            })?;
+        warnings.extend(world_warnings);
+    }
+
+    println!("Warnings: {}", warnings.len());
+    for warning in warnings {
+        println!("  {}", warning);
+    }
+
+    Ok(())
+}
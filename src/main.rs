@@ -4,111 +4,361 @@ use async_std::sync::Mutex;
 use async_std::task;
 use futures::future::join_all;
 use log;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 use structopt::StructOpt;
 
-use crate::ac::AnimatedCorpse;
-use crate::zone::Zone;
+use crate::clock::GameClock;
+use crate::config::Config;
+use crate::message::ZoneCoordinates;
+use crate::registry::{ZoneLoader, Zones};
+use crate::scheduler::{OverrunPolicy, Scheduler};
+use crate::tile::ParseMode;
 
 mod ac;
+mod auth;
 mod behavior;
 mod client;
+mod clock;
+mod config;
 mod error;
 mod event;
+mod grid;
+mod inspect;
 mod message;
 mod model;
+mod occupancy;
+mod registry;
+mod scheduler;
 mod socket;
+mod source;
 mod tile;
 mod util;
+mod viewer;
 mod world;
 mod zone;
 
-const TICK_EACH_MS: u64 = 5000;
-
+/// Command line arguments override configuration file and ROLLAC_* environment variables
 #[derive(StructOpt, Debug)]
 #[structopt(name = "basic")]
 struct Opt {
-    #[structopt(name = "host", default_value = "127.0.0.1")]
-    host: String,
+    #[structopt(name = "host")]
+    host: Option<String>,
+
+    #[structopt(name = "port")]
+    port: Option<u16>,
+
+    #[structopt(name = "disable_auth_token")]
+    disable_auth_token: Option<String>,
 
-    #[structopt(name = "port", default_value = "5000")]
-    port: u16,
+    /// Service account token (sent as bearer)
+    #[structopt(long)]
+    auth_token: Option<String>,
 
-    #[structopt(name = "disable_auth_token", default_value = "1234")]
-    disable_auth_token: String,
+    /// Service account login (sent as basic auth with ROLLAC_PASSWORD or configured password)
+    #[structopt(long)]
+    username: Option<String>,
 
+    /// Use https and wss (with server certificate verification)
     #[structopt(short, long)]
     secure: bool,
+
+    /// PEM CA certificate(s) to trust in addition of system ones (like self-signed server)
+    #[structopt(long, parse(from_os_str))]
+    ca_file: Option<PathBuf>,
+
+    /// Configuration file (default is ROLLAC_CONFIG or rollac.toml if exists)
+    #[structopt(short, long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Print resulting configuration and exit
+    #[structopt(long)]
+    print_config: bool,
+
+    /// Logging filter (like RUST_LOG, which stay prioritized)
+    #[structopt(long)]
+    log: Option<String>,
+
+    /// Render given zone (ROW.COL) in terminal and refresh it on each change
+    #[structopt(long, parse(try_from_str = util::parse_zone_coordinates))]
+    view: Option<ZoneCoordinates>,
+
+    /// Don't load zones at startup but when a message or event is addressed to them
+    #[structopt(long)]
+    lazy_zones: bool,
+
+    /// Handle only given zone (ROW.COL), can be repeated
+    #[structopt(long)]
+    only_zone: Vec<String>,
+
+    /// Api request connection timeout
+    #[structopt(long)]
+    http_connect_timeout_ms: Option<u64>,
+
+    /// Api request (whole) timeout
+    #[structopt(long)]
+    http_timeout_ms: Option<u64>,
+
+    /// Maximum retries of an api request which failed with a server side or availability error
+    #[structopt(long)]
+    http_retries: Option<u32>,
+
+    /// Delay before first api request retry, doubled at each next retry
+    #[structopt(long)]
+    http_retry_backoff_ms: Option<u64>,
+
+    /// Interval between two animations of a zone
+    #[structopt(long)]
+    tick_each_ms: Option<u64>,
+
+    /// When animation is late: "skip" missed ticks or "catch-up" them
+    #[structopt(long)]
+    tick_overrun: Option<OverrunPolicy>,
+
+    /// Maximum random offset of each zone tick (default is tick interval)
+    #[structopt(long)]
+    tick_jitter_ms: Option<u64>,
+
+    /// Real duration of a game day (day and night)
+    #[structopt(long)]
+    clock_day_length_ms: Option<u64>,
+
+    /// On unknown world or zone source chars: "lenient" (use default tile) or "strict" (fail)
+    #[structopt(long)]
+    source_mode: Option<ParseMode>,
+
+    /// Forget not acknowledged move requests after this delay
+    #[structopt(long)]
+    pending_move_timeout_ms: Option<u64>,
+
+    /// Seed of animated corpses traits generation (change it to give new traits)
+    #[structopt(long)]
+    traits_seed: Option<u64>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-async fn daemon() -> Result<(), error::Error> {
-    let opt = Opt::from_args();
-    let host: String = opt.host;
-    let port: u16 = opt.port;
-    let secure: bool = opt.secure;
-    let disable_auth_token: String = opt.disable_auth_token;
-    let protocol = if secure { "https" } else { "http" };
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Run the daemon (default)
+    Daemon,
+    /// Print the world grid and its legend
+    World,
+    /// Print tiles, builds, characters and animated corpses of a zone
+    Zone { row: u32, col: u32 },
+    /// List animated corpses of each zone with their type and behaviors
+    Acs,
+    /// Check a local world or zone source file and print its blocks
+    Source {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Print the file back in normalized source format
+        #[structopt(long)]
+        format: bool,
+    },
+}
+
+fn config(opt: &Opt) -> Result<Config, error::Error> {
+    let mut config = Config::load(opt.config.as_deref())?;
+
+    if let Some(host) = &opt.host {
+        config.connection.host = host.clone();
+    }
+    if let Some(port) = opt.port {
+        config.connection.port = port;
+    }
+    if let Some(disable_auth_token) = &opt.disable_auth_token {
+        config.connection.disable_auth_token = Some(disable_auth_token.clone());
+    }
+    if let Some(auth_token) = &opt.auth_token {
+        config.connection.auth_token = Some(auth_token.clone());
+    }
+    if let Some(username) = &opt.username {
+        config.connection.username = Some(username.clone());
+    }
+    if opt.secure {
+        config.connection.secure = true;
+    }
+    if let Some(ca_file) = &opt.ca_file {
+        config.connection.ca_file = Some(ca_file.clone());
+    }
+    if let Some(log) = &opt.log {
+        config.log.filter = log.clone();
+    }
+    if opt.lazy_zones {
+        config.zones.lazy = true;
+    }
+    if !opt.only_zone.is_empty() {
+        config.zones.only = opt.only_zone.clone();
+    }
+    if let Some(http_connect_timeout_ms) = opt.http_connect_timeout_ms {
+        config.http.connect_timeout_ms = http_connect_timeout_ms;
+    }
+    if let Some(http_timeout_ms) = opt.http_timeout_ms {
+        config.http.timeout_ms = http_timeout_ms;
+    }
+    if let Some(http_retries) = opt.http_retries {
+        config.http.retries = http_retries;
+    }
+    if let Some(http_retry_backoff_ms) = opt.http_retry_backoff_ms {
+        config.http.retry_backoff_ms = http_retry_backoff_ms;
+    }
+    if let Some(tick_each_ms) = opt.tick_each_ms {
+        config.tick.each_ms = tick_each_ms;
+    }
+    if let Some(tick_overrun) = opt.tick_overrun {
+        config.tick.overrun = tick_overrun;
+    }
+    if let Some(tick_jitter_ms) = opt.tick_jitter_ms {
+        config.tick.jitter_ms = Some(tick_jitter_ms);
+    }
+    if let Some(clock_day_length_ms) = opt.clock_day_length_ms {
+        config.clock.day_length_ms = clock_day_length_ms;
+    }
+    if let Some(source_mode) = opt.source_mode {
+        config.source.mode = source_mode;
+    }
+    if let Some(pending_move_timeout_ms) = opt.pending_move_timeout_ms {
+        config.behavior.pending_move_timeout_ms = pending_move_timeout_ms;
+    }
+    if let Some(traits_seed) = opt.traits_seed {
+        config.behavior.traits_seed = traits_seed;
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+fn client(config: &Config) -> Result<client::Client, error::Error> {
+    Ok(client::Client::new(
+        &config.connection.host,
+        config.connection.port,
+        config.connection.secure,
+        config.auth(),
+        config.ca_certificate()?.as_deref(),
+        config.request_policy(),
+        config.behavior.traits_seed,
+    )?)
+}
+
+async fn daemon(config: Config, view: Option<ZoneCoordinates>) -> Result<(), error::Error> {
+    let client = client(&config)?;
+    let host: &str = &config.connection.host;
+    let port: u16 = config.connection.port;
+    let secure: bool = config.connection.secure;
+    let lazy_zones: bool = config.zones.lazy;
+    let source_mode: ParseMode = config.source.mode;
+    let only_zones: Vec<ZoneCoordinates> = config.only_zones();
+    let scheduler = Scheduler::new(
+        Duration::from_millis(config.tick.each_ms),
+        config.tick.overrun,
+        Duration::from_millis(config.tick.jitter_ms.unwrap_or(config.tick.each_ms)),
+    );
+    let pending_move_timeout = Duration::from_millis(config.behavior.pending_move_timeout_ms);
+    let clock = GameClock::new(Duration::from_millis(config.clock.day_length_ms));
+    let protocol = if secure { "wss" } else { "ws" };
 
     // Prepare required variables
-    let mut zones: Vec<Zone> = vec![];
-    let client = client::Client::new(&host, port, secure, disable_auth_token);
     let (channel_sender, channel_receiver) = unbounded();
+    let (viewer_sender, viewer_receiver) = unbounded();
+    let viewer_sender = view.map(|_| viewer_sender);
 
     // Connect to world socket
     let url = format!("{}://{}:{}/world/events", protocol, host, port);
     log::info!("Connect socket on {}", url);
-    let mut socket = socket::Channel::new(url);
+    let mut socket = socket::Channel::new(url, config.auth(), config.ca_certificate()?);
     socket.connect()?;
 
     // Grab world information
     log::info!("Retrieve world from api");
-    let world = world::new(&client)?;
+    let world = world::new(&client, source_mode)?;
 
     // Create zones and place animated corpses
-    let mut found_animated_corpses = 0;
-    for (world_row_i, row) in world.rows.iter().enumerate() {
-        for (world_col_i, _) in row.cols.iter().enumerate() {
-            let zone_animated_corpses: Vec<Box<dyn AnimatedCorpse + Send + Sync>> = client
-                .get_animated_corpses(world_row_i as u32, world_col_i as u32)
-                .expect("Error during grab of animated corpses");
-            log::info!(
-                "Found {} animated corpses for zone {}.{}",
-                zone_animated_corpses.len(),
-                world_row_i,
-                world_col_i
-            );
-            found_animated_corpses += zone_animated_corpses.len();
+    let loader = if lazy_zones {
+        Some(ZoneLoader {
+            world: world.clone(),
+            client: client.clone(),
+            mode: source_mode,
+        })
+    } else {
+        None
+    };
+    let mut zones = Zones::new(loader, only_zones.clone());
+    if !lazy_zones {
+        let mut found_animated_corpses = 0;
+        for ((world_row_i, world_col_i), _) in world.grid.iter() {
+            if !only_zones.is_empty() && !only_zones.contains(&(world_row_i, world_col_i)) {
+                continue;
+            }
+
             log::info!("Create zone {}.{}", world_row_i, world_col_i);
-            zones.push(zone::new(
-                &world,
-                &client,
-                world_row_i as u32,
-                world_col_i as u32,
-                zone_animated_corpses,
-            )?);
+            let zone = zone::load(&world, &client, world_row_i, world_col_i, source_mode)?;
+            found_animated_corpses += zone.animated_corpses.len();
+            zones.insert(zone);
         }
+        log::info!(
+            "Total of animated corpses found: {}",
+            found_animated_corpses
+        );
     }
-    log::info!(
-        "Total of animated corpses found: {}",
-        found_animated_corpses
-    );
 
-    let zones: Mutex<Vec<Zone>> = Mutex::new(zones);
+    let zones: Mutex<Zones> = Mutex::new(zones);
     let mut futures: Vec<Pin<Box<dyn futures::Future<Output = ()> + std::marker::Send>>> = vec![];
 
-    futures.push(Box::pin(event::on_events(&zones, &channel_sender, &socket)));
-    futures.push(Box::pin(ac::animate(&zones, &channel_sender)));
+    futures.push(Box::pin(event::on_events(&zones, &client, &channel_sender, &socket)));
+    futures.push(Box::pin(ac::animate(
+        &zones,
+        &channel_sender,
+        scheduler,
+        pending_move_timeout,
+        clock,
+    )));
     futures.push(Box::pin(message::on_messages(
         &zones,
+        &channel_sender,
         channel_receiver,
         &socket,
+        &viewer_sender,
     )));
+    if let Some(coordinates) = view {
+        futures.push(Box::pin(viewer::view(&zones, viewer_receiver, coordinates)));
+    }
 
     join_all(futures).await;
     Ok(())
 }
 
-fn main() -> Result<(), error::Error> {
-    env_logger::init();
-    task::block_on(daemon())?;
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<(), error::Error> {
+    let opt = Opt::from_args();
+    let config = config(&opt)?;
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(&config.log.filter))
+        .init();
+
+    if opt.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+
+    match opt.command {
+        None | Some(Command::Daemon) => task::block_on(daemon(config, opt.view))?,
+        Some(Command::World) => inspect::world(&client(&config)?, config.source.mode)?,
+        Some(Command::Zone { row, col }) => {
+            inspect::zone(&client(&config)?, row, col, config.source.mode)?
+        }
+        Some(Command::Acs) => inspect::animated_corpses(&client(&config)?, config.source.mode)?,
+        Some(Command::Source { file, format }) => {
+            inspect::source(&file, format, config.source.mode)?
+        }
+    }
+
     Ok(())
 }
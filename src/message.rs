@@ -1,9 +1,10 @@
-use async_std::channel::Receiver;
+use async_std::channel::{Receiver, Sender};
 use async_std::sync::Mutex;
 
 use crate::event::ZoneEvent;
-use crate::zone::Zone;
-use crate::{model, socket};
+use crate::registry;
+use crate::registry::Zones;
+use crate::{ac, model, socket};
 
 pub type ZoneRowI = u32;
 pub type ZoneColI = u32;
@@ -11,21 +12,48 @@ pub type WorldRowI = u32;
 pub type WorldColI = u32;
 pub type AnimatedCorpseId = u32;
 pub type CharacterId = String;
+/// From 0 (fed) to `ac::MAX_HUNGER` (starving)
+pub type Hunger = u8;
+/// From 0 (quenched) to `ac::MAX_THIRST` (parched)
+pub type Thirst = u8;
 pub type ZoneCoordinates = (WorldRowI, WorldColI);
 
 #[derive(Debug, Clone)]
 pub enum SendEventMessage {
     RequireAnimatedCorpseMove(AnimatedCorpseId, ZoneRowI, ZoneColI),
+    AnimatedCorpseEat(AnimatedCorpseId, ZoneRowI, ZoneColI),
+}
+
+/// Raised by a fleeing animated corpse, make its herd mates around flee too
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub animated_corpse_id: AnimatedCorpseId,
+    pub type_: ac::Type,
+    pub position: (ZoneRowI, ZoneColI),
+    pub threat: (ZoneRowI, ZoneColI),
+    /// 1.0 when raised by an animated corpse which saw threat, lower when propagated
+    pub strength: f32,
 }
 
 #[derive(Debug, Clone)]
 pub enum ZoneMessage {
     UpdateAnimatedCorpsePosition(AnimatedCorpseId, ZoneRowI, ZoneColI),
+    UpdateAnimatedCorpseHunger(AnimatedCorpseId, Hunger),
+    UpdateAnimatedCorpseThirst(AnimatedCorpseId, Thirst),
+    /// Animations count during which animated corpse stay where it is
+    UpdateAnimatedCorpseLinger(AnimatedCorpseId, u8),
+    UpdateAnimatedCorpseAsleep(AnimatedCorpseId, bool),
+    /// Emotion and its cooldown
+    UpdateAnimatedCorpseEmotion(AnimatedCorpseId, ac::Emotion, u8),
     UpdateCharacterPosition(CharacterId, ZoneRowI, ZoneColI),
     AddBuild(model::Build),
     AddCharacter(CharacterId, ZoneRowI, ZoneColI), // FIXME model::Character
-    AddAnimatedCorpse(AnimatedCorpseId),
+    /// Animated corpse already retrieved from api
+    AddAnimatedCorpse(ac::AnimatedCorpseBase),
+    /// Animated corpse left zone (like when it appears in another one)
+    RemoveAnimatedCorpse(AnimatedCorpseId),
     RemoveCharacter(CharacterId),
+    Alarm(Alarm),
 }
 
 #[derive(Debug, Clone)]
@@ -35,14 +63,22 @@ pub enum Message {
 }
 
 pub async fn on_messages(
-    zones: &Mutex<Vec<Zone>>,
+    zones: &Mutex<Zones>,
+    channel_sender: &Sender<Message>,
     channel_receiver: Receiver<Message>,
     socket: &socket::Channel,
+    viewer_sender: &Option<Sender<ZoneCoordinates>>,
 ) {
     log::info!("Listening on messages");
     while let Ok(message) = channel_receiver.recv().await {
         match message {
             Message::Event(event_message, (world_row_i, world_col_i)) => {
+                if let Some(zone) = zones.lock().await.get_mut((world_row_i, world_col_i)) {
+                    if !zone.accept_event_message(&event_message) {
+                        continue;
+                    }
+                }
+
                 socket
                     .send(ZoneEvent::from_message(
                         event_message,
@@ -52,10 +88,29 @@ pub async fn on_messages(
                     .await
             }
             Message::Zone(zone_message, (world_row_i, world_col_i)) => {
-                // FIXME BS: check zone match
-                for zone in zones.lock().await.iter_mut() {
-                    if zone.world_row_i == world_row_i && zone.world_col_i == world_col_i {
-                        zone.on_message(zone_message.clone())
+                // Zone can produce messages in reaction (like alarmed animated corpses moves)
+                let messages =
+                    match registry::lock_loaded(zones, (world_row_i, world_col_i), "message")
+                        .await
+                        .get_mut((world_row_i, world_col_i))
+                    {
+                        Some(zone) => zone.on_message(zone_message),
+                        None => vec![],
+                    };
+                for message in messages {
+                    if let Err(err) = channel_sender.send(message).await {
+                        log::error!("Message channel is closed (from on_messages): {}", err);
+                        break;
+                    }
+                }
+
+                if let Some(viewer_sender) = viewer_sender {
+                    if viewer_sender
+                        .send((world_row_i, world_col_i))
+                        .await
+                        .is_err()
+                    {
+                        log::error!("Viewer channel is closed (from on_messages)");
                     }
                 }
             }
@@ -1,6 +1,9 @@
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Build ids of crops, where animated corpses can eat
+pub const CROP_BUILD_IDS: &[&str] = &["CEREAL", "VEGETABLE", "FRUIT_TREE"];
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Build {
     pub id: u32,
@@ -10,6 +13,12 @@ pub struct Build {
     pub traversable: HashMap<String, bool>,
 }
 
+impl Build {
+    pub fn is_crop(&self) -> bool {
+        CROP_BUILD_IDS.contains(&self.build_id.as_str())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Character {
     pub id: String,
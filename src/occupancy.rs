@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::ac::pending::PendingMoves;
+use crate::ac::{AnimatedCorpse, Type};
+use crate::message::{AnimatedCorpseId, CharacterId, ZoneColI, ZoneRowI};
+use crate::model;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Occupant {
+    AnimatedCorpse(AnimatedCorpseId, Type),
+    Character(CharacterId),
+}
+
+/// Who is on (or about to move on) each zone tile
+#[derive(Debug, Default)]
+pub struct Occupancy {
+    tiles: HashMap<(ZoneRowI, ZoneColI), Vec<Occupant>>,
+}
+
+impl Occupancy {
+    pub fn new(
+        animated_corpses: &[Box<dyn AnimatedCorpse + Send + Sync>],
+        characters: &[model::Character],
+        pending_moves: &PendingMoves,
+    ) -> Self {
+        let mut occupancy = Self::default();
+        let mut types: HashMap<AnimatedCorpseId, Type> = HashMap::new();
+
+        for animated_corpse in animated_corpses {
+            types.insert(animated_corpse.id(), animated_corpse.type_());
+            occupancy.add(
+                (animated_corpse.zone_row_i(), animated_corpse.zone_col_i()),
+                Occupant::AnimatedCorpse(animated_corpse.id(), animated_corpse.type_()),
+            );
+        }
+
+        for character in characters {
+            occupancy.add(
+                (character.zone_row_i, character.zone_col_i),
+                Occupant::Character(character.id.clone()),
+            );
+        }
+
+        // Pending move destination is reserved until server acknowledge the move
+        for (animated_corpse_id, pending_move) in pending_moves.iter() {
+            if let Some(type_) = types.get(animated_corpse_id) {
+                occupancy.add(
+                    (pending_move.to_row_i, pending_move.to_col_i),
+                    Occupant::AnimatedCorpse(*animated_corpse_id, *type_),
+                );
+            }
+        }
+
+        occupancy
+    }
+
+    pub fn add(&mut self, position: (ZoneRowI, ZoneColI), occupant: Occupant) {
+        let occupants = self.tiles.entry(position).or_default();
+        if !occupants.contains(&occupant) {
+            occupants.push(occupant);
+        }
+    }
+
+    pub fn occupants(&self, position: (ZoneRowI, ZoneColI)) -> &[Occupant] {
+        self.tiles
+            .get(&position)
+            .map(|occupants| occupants.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Return true if given animated corpse can move on position according to its species
+    /// tile sharing policy
+    pub fn can_enter(
+        &self,
+        animated_corpse_id: AnimatedCorpseId,
+        type_: Type,
+        position: (ZoneRowI, ZoneColI),
+    ) -> bool {
+        self.occupants(position)
+            .iter()
+            .all(|occupant| match occupant {
+                Occupant::AnimatedCorpse(occupant_id, _) if *occupant_id == animated_corpse_id => {
+                    true
+                }
+                occupant => type_.can_share_tile_with(occupant),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_enter_free_tile() {
+        let occupancy = Occupancy::default();
+
+        assert!(occupancy.can_enter(1, Type::GOAT, (0, 0)));
+    }
+
+    #[test]
+    fn can_enter_own_tile() {
+        let mut occupancy = Occupancy::default();
+        occupancy.add((0, 0), Occupant::AnimatedCorpse(1, Type::GOAT));
+
+        assert!(occupancy.can_enter(1, Type::GOAT, (0, 0)));
+        assert!(!occupancy.can_enter(2, Type::GOAT, (0, 0)));
+    }
+
+    #[test]
+    fn can_enter_according_to_sharing_policy() {
+        let mut occupancy = Occupancy::default();
+        occupancy.add((0, 0), Occupant::AnimatedCorpse(1, Type::MOORHEN));
+        occupancy.add((0, 1), Occupant::Character("character".to_string()));
+
+        // Moorhens share tiles between them only
+        assert!(occupancy.can_enter(2, Type::MOORHEN, (0, 0)));
+        assert!(!occupancy.can_enter(2, Type::CRAB, (0, 0)));
+        // Nobody share tile with a character
+        assert!(!occupancy.can_enter(2, Type::MOORHEN, (0, 1)));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_std::sync::{Mutex, MutexGuard};
+use async_std::task;
+
+use crate::client::Client;
+use crate::message::ZoneCoordinates;
+use crate::tile::ParseMode;
+use crate::world::World;
+use crate::zone;
+use crate::zone::Zone;
+
+pub struct ZoneLoader {
+    pub world: World,
+    pub client: Client,
+    pub mode: ParseMode,
+}
+
+impl ZoneLoader {
+    fn load(&self, coordinates: ZoneCoordinates) -> Option<Zone> {
+        let (world_row_i, world_col_i) = coordinates;
+
+        if !self.world.grid.contains(coordinates) {
+            log::error!(
+                "Zone {}.{} is outside world, can't load it",
+                world_row_i,
+                world_col_i
+            );
+            return None;
+        }
+
+        log::info!("Lazy load zone {}.{}", world_row_i, world_col_i);
+        match zone::load(
+            &self.world,
+            &self.client,
+            world_row_i,
+            world_col_i,
+            self.mode,
+        ) {
+            Ok(zone) => Some(zone),
+            Err(err) => {
+                log::error!("Fail to load zone {}.{}: {}", world_row_i, world_col_i, err);
+                None
+            }
+        }
+    }
+}
+
+/// Loaded zones indexed by their world coordinates
+pub struct Zones {
+    zones: HashMap<ZoneCoordinates, Zone>,
+    loader: Option<Arc<ZoneLoader>>,
+    only: Vec<ZoneCoordinates>,
+    missing_zone_hits: HashMap<ZoneCoordinates, u64>,
+    /// Zones being loaded, to not load them twice
+    loading: HashSet<ZoneCoordinates>,
+}
+
+impl Zones {
+    /// Zones outside `only` (if not empty) are ignored
+    pub fn new(loader: Option<ZoneLoader>, only: Vec<ZoneCoordinates>) -> Self {
+        Self {
+            zones: HashMap::new(),
+            loader: loader.map(Arc::new),
+            only,
+            missing_zone_hits: HashMap::new(),
+            loading: HashSet::new(),
+        }
+    }
+
+    pub fn insert(&mut self, zone: Zone) {
+        self.zones
+            .insert((zone.world_row_i, zone.world_col_i), zone);
+    }
+
+    pub fn get(&self, coordinates: ZoneCoordinates) -> Option<&Zone> {
+        self.zones.get(&coordinates)
+    }
+
+    pub fn get_mut(&mut self, coordinates: ZoneCoordinates) -> Option<&mut Zone> {
+        self.zones.get_mut(&coordinates)
+    }
+
+    pub fn coordinates(&self) -> Vec<ZoneCoordinates> {
+        self.zones.keys().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    /// If zone at given coordinates is not loaded, report it and return zone loader if zone
+    /// must be loaded (zone loader is configured and zone is not already being loaded)
+    fn missing(&mut self, coordinates: ZoneCoordinates, reason: &str) -> Option<Arc<ZoneLoader>> {
+        if !self.only.is_empty() && !self.only.contains(&coordinates) {
+            return None;
+        }
+        if self.zones.contains_key(&coordinates) {
+            return None;
+        }
+
+        self.report_missing_zone(coordinates, reason);
+        if self.loading.contains(&coordinates) {
+            return None;
+        }
+        let loader = self.loader.clone()?;
+        self.loading.insert(coordinates);
+        Some(loader)
+    }
+
+    fn report_missing_zone(&mut self, coordinates: ZoneCoordinates, reason: &str) {
+        let hits = self.missing_zone_hits.entry(coordinates).or_insert(0);
+        *hits += 1;
+
+        // Warn on 1st, 2nd, 4th, 8th ... hit to not flood logs
+        if hits.is_power_of_two() {
+            log::warn!(
+                "Zone {}.{} is not loaded ({}), {} hit(s) so far",
+                coordinates.0,
+                coordinates.1,
+                reason,
+                hits
+            );
+        } else {
+            log::debug!(
+                "Zone {}.{} is not loaded ({}), {} hit(s) so far",
+                coordinates.0,
+                coordinates.1,
+                reason,
+                hits
+            );
+        }
+    }
+
+    /// Count of hits (messages or events) addressed to given zone while it was not loaded
+    pub fn missing_zone_hits(&self, coordinates: ZoneCoordinates) -> u64 {
+        *self.missing_zone_hits.get(&coordinates).unwrap_or(&0)
+    }
+
+    pub fn total_missing_zone_hits(&self) -> u64 {
+        self.missing_zone_hits.values().sum()
+    }
+}
+
+/// Lock zones, after loading zone at given coordinates if it is not loaded (and zone loader
+/// is configured). Loading is made of blocking api requests: it runs in a blocking task,
+/// without zones lock.
+pub async fn lock_loaded<'a>(
+    zones: &'a Mutex<Zones>,
+    coordinates: ZoneCoordinates,
+    reason: &str,
+) -> MutexGuard<'a, Zones> {
+    let mut zones_ = zones.lock().await;
+    let loader = match zones_.missing(coordinates, reason) {
+        Some(loader) => loader,
+        None => return zones_,
+    };
+    drop(zones_);
+
+    let zone = task::spawn_blocking(move || loader.load(coordinates)).await;
+    let mut zones_ = zones.lock().await;
+    zones_.loading.remove(&coordinates);
+    if let Some(zone) = zone {
+        zones_.insert(zone);
+    }
+    zones_
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Auth;
+    use crate::client::RequestPolicy;
+    use crate::source::Document;
+    use crate::tile::world::WorldTiles;
+    use std::time::Duration;
+
+    /// Loader of a 2x2 world which server is unreachable
+    fn loader() -> ZoneLoader {
+        let (document, _) =
+            Document::parse("::LEGEND\n. PLAIN*\n::GEO\n..\n..\n", ParseMode::Strict).unwrap();
+        let legend = WorldTiles::new(document.require_legend().unwrap());
+        let (world, _) =
+            World::new(document.require_geo().unwrap(), legend, ParseMode::Strict).unwrap();
+        let policy = RequestPolicy {
+            connect_timeout: Duration::from_millis(100),
+            timeout: Duration::from_millis(100),
+            retries: 0,
+            retry_backoff: Duration::from_millis(0),
+        };
+        let client = Client::new("127.0.0.1", 1, false, Auth::default(), None, policy, 0).unwrap();
+
+        ZoneLoader {
+            world,
+            client,
+            mode: ParseMode::Strict,
+        }
+    }
+
+    #[test]
+    fn missing_zone_hits_are_counted() {
+        let mut zones = Zones::new(None, vec![]);
+
+        assert!(zones.missing((0, 0), "test").is_none());
+        assert!(zones.missing((0, 0), "test").is_none());
+        assert!(zones.missing((1, 1), "test").is_none());
+
+        assert_eq!(zones.missing_zone_hits((0, 0)), 2);
+        assert_eq!(zones.missing_zone_hits((0, 1)), 0);
+        assert_eq!(zones.total_missing_zone_hits(), 3);
+    }
+
+    #[test]
+    fn missing_zone_is_loaded_once_at_a_time() {
+        let mut zones = Zones::new(Some(loader()), vec![]);
+
+        assert!(zones.missing((0, 0), "test").is_some());
+        // Already being loaded
+        assert!(zones.missing((0, 0), "test").is_none());
+        assert_eq!(zones.missing_zone_hits((0, 0)), 2);
+    }
+
+    #[test]
+    fn zones_outside_only_are_never_loaded() {
+        let mut zones = Zones::new(Some(loader()), vec![(0, 0)]);
+
+        assert!(zones.missing((1, 1), "test").is_none());
+        assert_eq!(zones.total_missing_zone_hits(), 0);
+        assert!(zones.missing((0, 0), "test").is_some());
+    }
+
+    #[test]
+    fn lock_loaded_without_reachable_server() {
+        let zones = Mutex::new(Zones::new(Some(loader()), vec![(0, 0)]));
+
+        task::block_on(async {
+            let zones_ = lock_loaded(&zones, (1, 1), "test").await;
+            assert_eq!(zones_.len(), 0);
+            assert_eq!(zones_.total_missing_zone_hits(), 0);
+            drop(zones_);
+
+            // Load fails, zone can be loaded again at next hit
+            let mut zones_ = lock_loaded(&zones, (0, 0), "test").await;
+            assert_eq!(zones_.len(), 0);
+            assert!(zones_.missing((0, 0), "test").is_some());
+        });
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::message::ZoneCoordinates;
+
+/// What to do with ticks which should have been fired while previous tick was too long
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverrunPolicy {
+    /// Fire missed ticks as soon as possible
+    CatchUp,
+    /// Forget missed ticks and fire next tick at its scheduled instant
+    Skip,
+}
+
+impl FromStr for OverrunPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "catch-up" => Ok(OverrunPolicy::CatchUp),
+            "skip" => Ok(OverrunPolicy::Skip),
+            _ => Err(format!(
+                "Unknown overrun policy '{}', expected 'catch-up' or 'skip'",
+                value
+            )),
+        }
+    }
+}
+
+struct ZoneTick {
+    next: Instant,
+    count: u64,
+}
+
+/// Decide when each zone must be animated. Each zone get a random offset (jitter) at
+/// registration so all zones don't tick at the same instant.
+pub struct Scheduler {
+    tick_each: Duration,
+    overrun_policy: OverrunPolicy,
+    jitter: Duration,
+    ticks: HashMap<ZoneCoordinates, ZoneTick>,
+}
+
+impl Scheduler {
+    pub fn new(tick_each: Duration, overrun_policy: OverrunPolicy, jitter: Duration) -> Self {
+        Self {
+            tick_each,
+            overrun_policy,
+            jitter,
+            ticks: HashMap::new(),
+        }
+    }
+
+    /// Register zones which are not known yet by scheduler
+    pub fn register(&mut self, coordinates: &[ZoneCoordinates]) {
+        let now = Instant::now();
+        let tick_each = self.tick_each;
+        let jitter_ms = self.jitter.as_millis() as u64;
+
+        for zone_coordinates in coordinates {
+            self.ticks.entry(*zone_coordinates).or_insert_with(|| {
+                let offset = if jitter_ms > 0 {
+                    Duration::from_millis(rand::thread_rng().gen_range(0..jitter_ms))
+                } else {
+                    Duration::from_millis(0)
+                };
+                ZoneTick {
+                    next: now + tick_each + offset,
+                    count: 0,
+                }
+            });
+        }
+    }
+
+    /// Return zones to animate now with their tick count and schedule their next tick
+    pub fn due(&mut self, now: Instant) -> Vec<(ZoneCoordinates, u64)> {
+        let mut due = vec![];
+
+        for (zone_coordinates, tick) in self.ticks.iter_mut() {
+            if tick.next > now {
+                continue;
+            }
+
+            due.push((*zone_coordinates, tick.count));
+            let late = now - tick.next;
+            let missed = (late.as_millis() / self.tick_each.as_millis().max(1)) as u32;
+            if missed > 0 {
+                log::warn!(
+                    "Zone {}.{} tick {} is late of {} ms ({} tick(s) missed, {:?})",
+                    zone_coordinates.0,
+                    zone_coordinates.1,
+                    tick.count,
+                    late.as_millis(),
+                    missed,
+                    self.overrun_policy
+                );
+            }
+
+            match self.overrun_policy {
+                OverrunPolicy::CatchUp => {
+                    tick.next += self.tick_each;
+                    tick.count += 1;
+                }
+                OverrunPolicy::Skip => {
+                    tick.next += self.tick_each * (missed + 1);
+                    tick.count += missed as u64 + 1;
+                }
+            }
+        }
+
+        due
+    }
+
+    /// Duration to wait before next zone tick
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.ticks
+            .values()
+            .map(|tick| tick.next.saturating_duration_since(now))
+            .min()
+            .unwrap_or(self.tick_each)
+    }
+
+    pub fn tick_each(&self) -> Duration {
+        self.tick_each
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(100);
+
+    fn scheduler(overrun_policy: OverrunPolicy) -> (Scheduler, Instant) {
+        let mut scheduler = Scheduler::new(TICK, overrun_policy, Duration::from_millis(0));
+        scheduler.register(&[(0, 0)]);
+        // After registration, so first tick is due at most one tick later
+        (scheduler, Instant::now())
+    }
+
+    #[test]
+    fn nothing_due_before_first_tick() {
+        let (mut scheduler, start) = scheduler(OverrunPolicy::CatchUp);
+
+        assert!(scheduler.due(start).is_empty());
+        assert!(scheduler.until_next(start) <= TICK);
+    }
+
+    #[test]
+    fn catch_up_fire_missed_ticks() {
+        let (mut scheduler, start) = scheduler(OverrunPolicy::CatchUp);
+        let late = start + TICK * 4;
+
+        assert_eq!(scheduler.due(late), vec![((0, 0), 0)]);
+        assert_eq!(scheduler.due(late), vec![((0, 0), 1)]);
+        assert_eq!(scheduler.due(late), vec![((0, 0), 2)]);
+    }
+
+    #[test]
+    fn skip_forget_missed_ticks() {
+        let (mut scheduler, start) = scheduler(OverrunPolicy::Skip);
+        let late = start + TICK * 4;
+
+        assert_eq!(scheduler.due(late), vec![((0, 0), 0)]);
+        assert!(scheduler.due(late).is_empty());
+        assert_eq!(scheduler.due(late + TICK), vec![((0, 0), 4)]);
+    }
+
+    #[test]
+    fn overrun_policy_from_str() {
+        assert_eq!("skip".parse(), Ok(OverrunPolicy::Skip));
+        assert_eq!("catch-up".parse(), Ok(OverrunPolicy::CatchUp));
+        assert!("later".parse::<OverrunPolicy>().is_err());
+    }
+}
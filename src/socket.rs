@@ -3,21 +3,37 @@ extern crate websocket;
 use self::websocket::OwnedMessage;
 use async_std::channel::{unbounded, Receiver, Sender};
 use async_std::task;
+use native_tls::{Certificate, TlsConnector, TlsStream};
+use std::io::{self, BufReader, Cursor, ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
 use std::time::SystemTime;
+use websocket::header::Headers;
+use websocket::receiver::Receiver as WsReceiver;
+use websocket::result::{WebSocketOtherError, WebSocketResult};
+use websocket::sender::Sender as WsSender;
+use websocket::sync::Writer;
+use websocket::ws::Receiver as _;
 use websocket::{ClientBuilder, Message, WebSocketError};
 
 use self::websocket::client::sync::Client;
+use crate::auth::Auth;
 use crate::error;
 use crate::event;
 use std::net::TcpStream;
 
+/// Secure websocket reads wait for data at most this delay before letting writer use the
+/// stream
+const WSS_READ_WAIT_MS: u64 = 50;
+
 pub struct Channel {
     ws_address: String,
+    auth: Auth,
+    ca_certificate: Option<Vec<u8>>,
     from_main_sender: Sender<event::ZoneEvent>,
     from_main_receiver: Arc<Mutex<Receiver<event::ZoneEvent>>>,
     from_websocket_sender: Arc<Mutex<Sender<event::ZoneEvent>>>,
@@ -30,7 +46,9 @@ pub struct Channel {
 }
 
 impl Channel {
-    pub fn new(ws_address: String) -> Self {
+    /// Use TLS if `ws_address` is a wss:// url, verifying server certificate with system
+    /// roots and given (PEM) CA certificate
+    pub fn new(ws_address: String, auth: Auth, ca_certificate: Option<Vec<u8>>) -> Self {
         let (from_main_sender, from_main_receiver) = unbounded();
         let (from_websocket_sender, from_websocket_receiver) = unbounded();
         let from_main_receiver = Arc::new(Mutex::new(from_main_receiver));
@@ -40,6 +58,8 @@ impl Channel {
 
         Self {
             ws_address,
+            auth,
+            ca_certificate,
             from_main_sender,
             from_main_receiver,
             ws_reader_handle: None,
@@ -52,28 +72,135 @@ impl Channel {
         }
     }
 
+    fn is_secure(&self) -> bool {
+        self.ws_address.starts_with("wss://")
+    }
+
+    /// Websocket handshake headers, authenticated according to channel auth
+    fn headers(&self) -> Headers {
+        let mut headers = Headers::new();
+        for (name, value) in self.auth.headers() {
+            headers.set_raw(name, vec![value.into_bytes()]);
+        }
+        headers
+    }
+
     fn create_ws_client(&mut self) -> Result<Client<TcpStream>, error::Error> {
         match ClientBuilder::new(self.ws_address.as_str()) {
-            Ok(mut ws_client) => match ws_client.connect_insecure() {
+            Ok(ws_client) => match ws_client.custom_headers(&self.headers()).connect_insecure() {
                 Ok(ws_client) => Ok(ws_client),
                 Err(err) => {
-                    return Err(error::Error::new(format!(
-                        "Fail to connect websocket: {}",
-                        err
-                    )))
+                    return Err(error::Error::socket(
+                        format!("Fail to connect websocket: {}", err),
+                        Some(Box::new(err)),
+                    ))
                 }
             },
-            Err(err) => return Err(error::Error::new(format!("Bad websocket url: {}", err))),
+            Err(err) => {
+                return Err(error::Error::config(
+                    format!("Bad websocket url: {}", err),
+                    Some(Box::new(err)),
+                ))
+            }
+        }
+    }
+
+    fn create_wss_client(&mut self) -> Result<Client<TlsStream<TcpStream>>, error::Error> {
+        let mut tls_connector = TlsConnector::builder();
+        if let Some(ca_certificate) = &self.ca_certificate {
+            let certificate = Certificate::from_pem(ca_certificate)
+                .map_err(|err| {
+                    error::Error::config(
+                        format!("Invalid CA certificate: {}", err),
+                        Some(Box::new(err)),
+                    )
+                })?;
+            tls_connector.add_root_certificate(certificate);
+        }
+        let tls_connector = tls_connector
+            .build()
+            .map_err(|err| {
+                error::Error::socket(
+                    format!("TLS is not available: {}", err),
+                    Some(Box::new(err)),
+                )
+            })?;
+
+        match ClientBuilder::new(self.ws_address.as_str()) {
+            Ok(ws_client) => match ws_client
+                .custom_headers(&self.headers())
+                .connect_secure(Some(tls_connector))
+            {
+                Ok(ws_client) => Ok(ws_client),
+                Err(err) if is_tls_error(&err) => Err(error::Error::socket(
+                    format!(
+                        "TLS was requested but TLS connection to {} failed \
+                        (does server accept TLS and is its certificate trusted ?): {}",
+                        self.ws_address, err
+                    ),
+                    Some(Box::new(err)),
+                )),
+                Err(err) => Err(error::Error::socket(
+                    format!("Fail to connect secure websocket: {}", err),
+                    Some(Box::new(err)),
+                )),
+            },
+            Err(err) => Err(error::Error::config(
+                format!("Bad websocket url: {}", err),
+                Some(Box::new(err)),
+            )),
         }
     }
 
     pub fn connect(&mut self) -> Result<(), error::Error> {
+        if self.is_secure() {
+            return self.connect_secure();
+        }
+
+        let ws_client = self.create_ws_client()?;
+        let (mut ws_reader, ws_writer) = ws_client.split()?;
+        self.spawn(move || ws_reader.recv_message(), ws_writer);
+
+        Ok(())
+    }
+
+    /// TLS stream can't be split in reader and writer: share it between them (see
+    /// `SharedTlsStream`)
+    fn connect_secure(&mut self) -> Result<(), error::Error> {
+        let ws_client = self.create_wss_client()?;
+        ws_client
+            .stream_ref()
+            .get_ref()
+            .set_read_timeout(Some(Duration::from_millis(WSS_READ_WAIT_MS)))?;
+        // Bytes already read with handshake response must be read first
+        let (stream, buffer) = ws_client.into_stream();
+        let buffered = buffer
+            .map(|(buffer, position, capacity)| buffer[position..capacity].to_vec())
+            .unwrap_or_default();
+        let stream = SharedTlsStream::new(stream);
+
+        let mut ws_reader = BufReader::new(Cursor::new(buffered).chain(stream.clone()));
+        let mut ws_receiver = WsReceiver::new(false);
+        let ws_writer = Writer {
+            stream,
+            sender: WsSender::new(true),
+        };
+        self.spawn(move || ws_receiver.recv_message(&mut ws_reader), ws_writer);
+
+        Ok(())
+    }
+
+    /// Start websocket reader thread (reading messages with given blocking function) and
+    /// websocket writer thread
+    fn spawn<R, W>(&mut self, mut recv_message: R, mut ws_writer: W)
+    where
+        R: FnMut() -> WebSocketResult<OwnedMessage> + Send + 'static,
+        W: MessageWriter + Send + 'static,
+    {
         let from_main_receiver = Arc::clone(&self.from_main_receiver);
         let from_websocket_sender = Arc::clone(&self.from_websocket_sender);
         let ws_reader_closed = Arc::clone(&self.ws_reader_closed);
         let ws_sender_closed = Arc::clone(&self.ws_sender_closed);
-        let ws_client = self.create_ws_client()?;
-        let (mut ws_reader, mut ws_writer) = ws_client.split()?;
 
         // ws reader
         let ws_reader_handle = thread::spawn(move || {
@@ -81,47 +208,7 @@ impl Channel {
                 let from_websocket_sender = from_websocket_sender
                     .lock()
                     .expect("Fail to acquire from_websocket_sender lock");
-                for message in ws_reader.incoming_messages() {
-                    match message {
-                        Ok(OwnedMessage::Text(message)) => {
-                            match serde_json::from_str(&message) {
-                                Ok(value) => match event::ZoneEvent::from_value(value) {
-                                    Ok(event) => {
-                                        if let event::ZoneEventType::ServerPermitClose =
-                                            event.event_type
-                                        {
-                                            log::info!("Receive close event from websocket");
-                                            break;
-                                        }
-
-                                        if let Err(_) = from_websocket_sender.send(event).await {
-                                            log::error!(
-                                            "Something went wrong during process of received event"
-                                            );
-                                            break;
-                                        }
-                                    }
-                                    Err(err) => log::error!("Error while decoding event: {}", err),
-                                },
-                                Err(err) => {
-                                    log::error!("Error when interpreting event as str: {}", err)
-                                }
-                            };
-                        }
-                        Ok(OwnedMessage::Close(_)) => {
-                            log::info!("Close web socket message received");
-                            break;
-                        }
-                        Err(WebSocketError::NoDataAvailable) => {
-                            log::error!("Web socket error: NoDataAvailable");
-                            break;
-                        }
-                        _ => log::error!(
-                            "WebSocket(receiver): Unknown websocket message received: {:?}",
-                            message
-                        ), // TODO add ping/pong (OwnedMessage::ping|pong)
-                    }
-                }
+                while on_ws_message(recv_message(), &from_websocket_sender).await {}
             });
 
             let mut closed = ws_reader_closed
@@ -139,21 +226,7 @@ impl Channel {
                     .expect("Fail to acquire from_main_receiver lock");
 
                 while let Ok(received) = from_main_receiver.recv().await {
-                    let message_json_str = match serde_json::to_string(&received) {
-                        Ok(message_json_str) => message_json_str,
-                        Err(err) => {
-                            log::error!("Error during serialisation of event: {}", err);
-                            continue;
-                        }
-                    };
-                    let message = Message::text(message_json_str);
-                    if let Err(err) = ws_writer.send_message(&message) {
-                        log::error!("Error during send message: {}", err);
-                    };
-
-                    if let event::ZoneEventType::ClientWantClose = received.event_type {
-                        // Get out for loop (and finish thread)
-                        log::info!("Web socket writer is closing ...");
+                    if !send_event(&mut ws_writer, &received) {
                         break;
                     }
                 }
@@ -168,8 +241,6 @@ impl Channel {
 
         self.ws_reader_handle = Some(ws_reader_handle);
         self.ws_sender_handle = Some(ws_sender_handle);
-
-        Ok(())
     }
 
     pub async fn send(&self, event: event::ZoneEvent) {
@@ -217,3 +288,264 @@ impl Channel {
         Ok(())
     }
 }
+
+fn is_tls_error(err: &WebSocketError) -> bool {
+    match err {
+        WebSocketError::Other(err) => matches!(
+            err.downcast_ref::<WebSocketOtherError>(),
+            Some(WebSocketOtherError::TlsError(_))
+                | Some(WebSocketOtherError::TlsHandshakeFailure)
+                | Some(WebSocketOtherError::TlsHandshakeInterruption)
+        ),
+        _ => false,
+    }
+}
+
+/// Transmit received websocket message as event. Return false if websocket must be closed.
+async fn on_ws_message(
+    message: WebSocketResult<OwnedMessage>,
+    from_websocket_sender: &Sender<event::ZoneEvent>,
+) -> bool {
+    match message {
+        Ok(OwnedMessage::Text(message)) => {
+            match serde_json::from_str(&message) {
+                Ok(value) => match event::ZoneEvent::from_value(value) {
+                    Ok(event) => {
+                        if let event::ZoneEventType::ServerPermitClose = event.event_type {
+                            log::info!("Receive close event from websocket");
+                            return false;
+                        }
+
+                        if from_websocket_sender.send(event).await.is_err() {
+                            log::error!("Something went wrong during process of received event");
+                            return false;
+                        }
+                    }
+                    Err(err) => log::error!("Error while decoding event: {}", err),
+                },
+                Err(err) => {
+                    log::error!("Error when interpreting event as str: {}", err)
+                }
+            };
+        }
+        Ok(OwnedMessage::Close(_)) => {
+            log::info!("Close web socket message received");
+            return false;
+        }
+        Err(WebSocketError::NoDataAvailable) => {
+            log::error!("Web socket error: NoDataAvailable");
+            return false;
+        }
+        Err(WebSocketError::IoError(err)) => {
+            log::error!("Web socket error: {}", err);
+            return false;
+        }
+        _ => log::error!(
+            "WebSocket(receiver): Unknown websocket message received: {:?}",
+            message
+        ), // TODO add ping/pong (OwnedMessage::ping|pong)
+    }
+
+    true
+}
+
+/// Send event through websocket. Return false if websocket writing must be stopped.
+fn send_event<W: MessageWriter>(ws_writer: &mut W, event: &event::ZoneEvent) -> bool {
+    let message_json_str = match serde_json::to_string(event) {
+        Ok(message_json_str) => message_json_str,
+        Err(err) => {
+            log::error!("Error during serialisation of event: {}", err);
+            return true;
+        }
+    };
+    let message = Message::text(message_json_str);
+    if let Err(err) = ws_writer.send_message(&message) {
+        log::error!("Error during send message: {}", err);
+    };
+
+    if let event::ZoneEventType::ClientWantClose = event.event_type {
+        // Get out for loop (and finish thread)
+        log::info!("Web socket writer is closing ...");
+        return false;
+    }
+
+    true
+}
+
+/// Websocket writers which can send a message
+trait MessageWriter {
+    fn send_message(&mut self, message: &Message) -> WebSocketResult<()>;
+}
+
+impl<W: Write> MessageWriter for Writer<W> {
+    fn send_message(&mut self, message: &Message) -> WebSocketResult<()> {
+        Writer::send_message(self, message)
+    }
+}
+
+/// TLS stream shared by secure websocket reader and writer. Stream read timeout is short:
+/// reads release the stream at each timeout (giving way to waiting writes) and try again,
+/// so websocket frames reader only see a blocking stream and never lose a partial frame.
+#[derive(Clone)]
+struct SharedTlsStream {
+    stream: Arc<Mutex<TlsStream<TcpStream>>>,
+    waiting_writers: Arc<AtomicUsize>,
+}
+
+impl SharedTlsStream {
+    fn new(stream: TlsStream<TcpStream>) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(stream)),
+            waiting_writers: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn with_stream<T>(
+        &self,
+        f: impl FnOnce(&mut TlsStream<TcpStream>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut stream = self
+            .stream
+            .lock()
+            .map_err(|_| io::Error::other("TLS stream lock is poisoned"))?;
+        f(&mut stream)
+    }
+
+    fn write_with<T>(
+        &self,
+        f: impl FnOnce(&mut TlsStream<TcpStream>) -> io::Result<T>,
+    ) -> io::Result<T> {
+        self.waiting_writers.fetch_add(1, Ordering::SeqCst);
+        let result = self.with_stream(f);
+        self.waiting_writers.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+}
+
+impl Read for SharedTlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.waiting_writers.load(Ordering::SeqCst) > 0 {
+                thread::yield_now();
+                continue;
+            }
+
+            match self.with_stream(|stream| stream.read(buf)) {
+                // TLS read is resumed where it stopped
+                Err(err)
+                    if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Write for SharedTlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|stream| stream.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_with(|stream| stream.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use native_tls::{Identity, TlsAcceptor};
+    use std::net::TcpListener;
+    use websocket::sync::server::IntoWs;
+
+    /// Self-signed certificate of a stand-in secure server at localhost
+    const CERTIFICATE: &[u8] = include_bytes!("../tests/fixtures/localhost.crt");
+    const IDENTITY: &[u8] = include_bytes!("../tests/fixtures/localhost.p12");
+    const IDENTITY_PASSWORD: &str = "rollac";
+
+    /// Accept one secure websocket connection, then give its TLS stream to `serve`
+    fn stand_in_server<T: Send + 'static>(
+        serve: impl FnOnce(TlsStream<TcpStream>) -> T + Send + 'static,
+    ) -> (u16, JoinHandle<Option<T>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let identity = Identity::from_pkcs12(IDENTITY, IDENTITY_PASSWORD).unwrap();
+        let acceptor = TlsAcceptor::new(identity).unwrap();
+
+        let handle = thread::spawn(move || {
+            let (tcp_stream, _) = listener.accept().unwrap();
+            let tls_stream = acceptor.accept(tcp_stream).ok()?;
+            let client = tls_stream.into_ws().ok()?.accept().ok()?;
+            Some(serve(client.into_stream().0))
+        });
+
+        (port, handle)
+    }
+
+    fn channel(port: u16, ca_certificate: Option<Vec<u8>>) -> Channel {
+        Channel::new(
+            format!("wss://localhost:{}/ws", port),
+            Auth::new(None, None),
+            ca_certificate,
+        )
+    }
+
+    #[test]
+    fn secure_channel_read_split_frame_and_write() {
+        let event = r#"{"type":"PLAYER_MOVE","world_row_i":1,"world_col_i":2,"data":{"to_row_i":3,"to_col_i":4,"character_id":"abc"}}"#;
+        let (port, server) = stand_in_server(move |mut stream| {
+            // Send a frame in two parts, second one after client read wait
+            let mut frame = vec![0x81, event.len() as u8];
+            frame.extend(event.as_bytes());
+            let (begin, end) = frame.split_at(frame.len() / 2);
+            stream.write_all(begin).unwrap();
+            stream.flush().unwrap();
+            thread::sleep(Duration::from_millis(WSS_READ_WAIT_MS * 4));
+            stream.write_all(end).unwrap();
+            stream.flush().unwrap();
+
+            WsReceiver::new(true)
+                .recv_message(&mut BufReader::new(&mut stream))
+                .unwrap()
+        });
+
+        let mut channel = channel(port, Some(CERTIFICATE.to_vec()));
+        channel.connect().unwrap();
+        let received = task::block_on(channel.from_websocket_receiver.recv()).unwrap();
+        task::block_on(channel.send(event::ZoneEvent {
+            event_type: event::ZoneEventType::AnimatedCorpseMove {
+                to_row_i: 5,
+                to_col_i: 6,
+                animated_corpse_id: 7,
+            },
+            event_type_name: String::from(event::ANIMATED_CORPSE_MOVE),
+            world_row_i: 1,
+            world_col_i: 2,
+        }));
+        let sent = server.join().unwrap().unwrap();
+
+        match received.event_type {
+            event::ZoneEventType::PlayerMove {
+                to_row_i,
+                to_col_i,
+                character_id,
+            } => assert_eq!((to_row_i, to_col_i, character_id.as_str()), (3, 4, "abc")),
+            event_type => panic!("Unexpected event {:?}", event_type),
+        }
+        match sent {
+            OwnedMessage::Text(text) => assert!(text.contains(event::ANIMATED_CORPSE_MOVE)),
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn secure_channel_refuse_untrusted_certificate() {
+        let (port, server) = stand_in_server(|_| ());
+
+        let result = channel(port, None).connect();
+        assert!(result.is_err());
+        assert!(server.join().unwrap().is_none());
+    }
+}
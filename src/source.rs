@@ -0,0 +1,644 @@
+//! World and zone source files: `::NAME` block headers followed by block lines, like:
+//!
+//! ```text
+//! ::LEGEND
+//! ~ SEA*
+//! ^ MOUNTAIN
+//! ::META
+//! SPAWN: RANDOM
+//! ::GEO
+//! ~~^
+//! ~^^
+//! ```
+//!
+//! META lines are `KEY: VALUE`, LEGEND lines are `CHAR TILE_ID` (`*` suffix marks default tile),
+//! GEO lines are tile chars. Other blocks are kept as raw lines.
+//!
+//! In lenient mode, like the former block extraction, content outside blocks is ignored, first
+//! of duplicated blocks is used, extra legend tokens are ignored and last of duplicated legend
+//! chars (or default tiles) wins; each of them give a warning. They are errors in strict mode.
+use crate::grid::{Grid, GridPosition};
+use crate::tile::ParseMode;
+use std::error;
+use std::fmt;
+
+pub const BLOCK_HEADER_PREFIX: &str = "::";
+pub const BLOCK_LEGEND: &str = "LEGEND";
+pub const BLOCK_META: &str = "META";
+pub const BLOCK_GEO: &str = "GEO";
+pub const DEFAULT_TILE_SUFFIX: char = '*';
+pub const GEO_PADDING: char = ' ';
+
+/// Position in source, line and column start at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl SourceError {
+    pub fn new(position: Position, message: String) -> Self {
+        Self { position, message }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+impl error::Error for SourceError {}
+
+/// Source problem which was worked around (like in lenient parse mode)
+pub type SourceWarning = SourceError;
+
+/// Collect source problems by char, to give one warning per char (at its first position)
+/// instead of one per cell
+#[derive(Debug, Default)]
+pub struct CharWarnings {
+    chars: Vec<(char, Position, usize)>,
+}
+
+impl CharWarnings {
+    pub fn add(&mut self, char_: char, position: Position) {
+        match self.chars.iter_mut().find(|(known, _, _)| *known == char_) {
+            Some((_, _, count)) => *count += 1,
+            None => self.chars.push((char_, position, 1)),
+        }
+    }
+
+    /// Warnings in order of first appearance, message is built from char and count
+    pub fn into_warnings<F>(self, message: F) -> Vec<SourceWarning>
+    where
+        F: Fn(char, usize) -> String,
+    {
+        self.chars
+            .into_iter()
+            .map(|(char_, position, count)| SourceWarning::new(position, message(char_, count)))
+            .collect()
+    }
+}
+
+/// Fail with given error in strict mode, collect it (with how it is worked around) as warning
+/// in lenient mode
+fn tolerate(
+    mode: ParseMode,
+    warnings: &mut Vec<SourceWarning>,
+    error: SourceError,
+    resolution: &str,
+) -> Result<(), SourceError> {
+    match mode {
+        ParseMode::Strict => Err(error),
+        ParseMode::Lenient => {
+            warnings.push(SourceWarning::new(
+                error.position,
+                format!("{}, {}", error.message, resolution),
+            ));
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaEntry {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub char_: char,
+    pub tile_id: String,
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Legend {
+    pub entries: Vec<LegendEntry>,
+}
+
+impl Legend {
+    pub fn tile_id(&self, char_: char) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.char_ == char_)
+            .map(|entry| entry.tile_id.as_str())
+    }
+
+    pub fn default_entry(&self) -> Option<&LegendEntry> {
+        self.entries.iter().find(|entry| entry.default)
+    }
+}
+
+/// Tile chars grid, as wide as longest line (ignoring trailing whitespaces). Shorter lines
+/// are padded with `GEO_PADDING`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geo {
+    /// Source line of first row
+    pub first_line: usize,
+    pub grid: Grid<char>,
+    /// Source length of each row, cells after it are padding
+    pub row_lengths: Vec<usize>,
+}
+
+impl Geo {
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    /// False for padding cells (and outside grid)
+    pub fn in_source(&self, position: GridPosition) -> bool {
+        self.row_lengths
+            .get(position.0 as usize)
+            .map(|length| (position.1 as usize) < *length)
+            .unwrap_or(false)
+    }
+
+    /// Source position of given grid position
+    pub fn position(&self, position: GridPosition) -> Position {
+        Position::new(
+            self.first_line + position.0 as usize,
+            position.1 as usize + 1,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockContent {
+    Meta(Vec<MetaEntry>),
+    Legend(Legend),
+    Geo(Geo),
+    Raw(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub name: String,
+    /// Source line of block header
+    pub line: usize,
+    pub content: BlockContent,
+}
+
+/// Parsed source file, blocks are kept in source order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+}
+
+impl Document {
+    /// Parse source, with warnings of problems tolerated by (lenient) mode
+    pub fn parse(source: &str, mode: ParseMode) -> Result<(Self, Vec<SourceWarning>), SourceError> {
+        let mut warnings: Vec<SourceWarning> = vec![];
+        let mut blocks = vec![];
+        let mut current: Option<(String, usize, Vec<&str>)> = None;
+
+        for (line_i, line) in source.lines().enumerate() {
+            let line_number = line_i + 1;
+
+            if let Some(name) = line.strip_prefix(BLOCK_HEADER_PREFIX) {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(SourceError::new(
+                        Position::new(line_number, BLOCK_HEADER_PREFIX.len() + 1),
+                        "Block name is missing".to_string(),
+                    ));
+                }
+                if let Some((name, header_line, lines)) = current.take() {
+                    blocks.push(parse_block(name, header_line, &lines, mode, &mut warnings)?);
+                }
+                current = Some((name.to_string(), line_number, vec![]));
+                continue;
+            }
+
+            match current.as_mut() {
+                Some((_, _, lines)) => lines.push(line),
+                None if line.trim().is_empty() => {}
+                None => tolerate(
+                    mode,
+                    &mut warnings,
+                    SourceError::new(
+                        Position::new(line_number, 1),
+                        format!(
+                            "Content outside of a block (expected '{}NAME' header)",
+                            BLOCK_HEADER_PREFIX
+                        ),
+                    ),
+                    "line is ignored",
+                )?,
+            }
+        }
+
+        if let Some((name, header_line, lines)) = current.take() {
+            blocks.push(parse_block(name, header_line, &lines, mode, &mut warnings)?);
+        }
+
+        let document = Self { blocks };
+        for name in &[BLOCK_META, BLOCK_LEGEND, BLOCK_GEO] {
+            let mut same_blocks = document.blocks.iter().filter(|block| block.name == *name);
+            if let (Some(_), Some(duplicate)) = (same_blocks.next(), same_blocks.next()) {
+                tolerate(
+                    mode,
+                    &mut warnings,
+                    SourceError::new(
+                        Position::new(duplicate.line, 1),
+                        format!("Block {} is defined twice", name),
+                    ),
+                    "first one is used",
+                )?;
+            }
+        }
+
+        Ok((document, warnings))
+    }
+
+    pub fn meta(&self) -> Option<&[MetaEntry]> {
+        self.blocks.iter().find_map(|block| match &block.content {
+            BlockContent::Meta(entries) => Some(entries.as_slice()),
+            _ => None,
+        })
+    }
+
+    pub fn legend(&self) -> Option<&Legend> {
+        self.blocks.iter().find_map(|block| match &block.content {
+            BlockContent::Legend(legend) => Some(legend),
+            _ => None,
+        })
+    }
+
+    pub fn geo(&self) -> Option<&Geo> {
+        self.blocks.iter().find_map(|block| match &block.content {
+            BlockContent::Geo(geo) => Some(geo),
+            _ => None,
+        })
+    }
+
+    /// Like `geo` but error if there is no GEO block
+    pub fn require_geo(&self) -> Result<&Geo, SourceError> {
+        self.geo().ok_or_else(|| {
+            SourceError::new(Position::new(1, 1), format!("Block {} not found", BLOCK_GEO))
+        })
+    }
+
+    /// Like `legend` but error if there is no LEGEND block
+    pub fn require_legend(&self) -> Result<&Legend, SourceError> {
+        self.legend().ok_or_else(|| {
+            SourceError::new(
+                Position::new(1, 1),
+                format!("Block {} not found", BLOCK_LEGEND),
+            )
+        })
+    }
+}
+
+/// Serialize document in source format
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.blocks {
+            writeln!(f, "{}{}", BLOCK_HEADER_PREFIX, block.name)?;
+            match &block.content {
+                BlockContent::Meta(entries) => {
+                    for entry in entries {
+                        writeln!(f, "{}: {}", entry.key, entry.value)?;
+                    }
+                }
+                BlockContent::Legend(legend) => {
+                    for entry in &legend.entries {
+                        let suffix = if entry.default {
+                            DEFAULT_TILE_SUFFIX.to_string()
+                        } else {
+                            "".to_string()
+                        };
+                        writeln!(f, "{} {}{}", entry.char_, entry.tile_id, suffix)?;
+                    }
+                }
+                BlockContent::Geo(geo) => {
+                    for (row, length) in geo.grid.rows().zip(geo.row_lengths.iter()) {
+                        writeln!(f, "{}", row[..*length].iter().collect::<String>())?;
+                    }
+                }
+                BlockContent::Raw(lines) => {
+                    for line in lines {
+                        writeln!(f, "{}", line)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_block(
+    name: String,
+    header_line: usize,
+    lines: &[&str],
+    mode: ParseMode,
+    warnings: &mut Vec<SourceWarning>,
+) -> Result<Block, SourceError> {
+    let first_line = header_line + 1;
+    let content = match name.as_str() {
+        BLOCK_META => BlockContent::Meta(parse_meta(first_line, lines)?),
+        BLOCK_LEGEND => BlockContent::Legend(parse_legend(first_line, lines, mode, warnings)?),
+        BLOCK_GEO => BlockContent::Geo(parse_geo(header_line, lines)?),
+        _ => BlockContent::Raw(lines.iter().map(|line| line.to_string()).collect()),
+    };
+
+    Ok(Block {
+        name,
+        line: header_line,
+        content,
+    })
+}
+
+fn parse_meta(first_line: usize, lines: &[&str]) -> Result<Vec<MetaEntry>, SourceError> {
+    let mut entries = vec![];
+
+    for (line_i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let separator_i = line.find(':').ok_or_else(|| {
+            SourceError::new(
+                Position::new(first_line + line_i, line.chars().count() + 1),
+                format!("Expected 'KEY: VALUE' meta line, got '{}'", line),
+            )
+        })?;
+        let key = line[..separator_i].trim();
+        if key.is_empty() {
+            return Err(SourceError::new(
+                Position::new(first_line + line_i, 1),
+                "Meta key is missing".to_string(),
+            ));
+        }
+
+        entries.push(MetaEntry {
+            key: key.to_string(),
+            value: line[separator_i + 1..].trim().to_string(),
+        });
+    }
+
+    Ok(entries)
+}
+
+fn parse_legend(
+    first_line: usize,
+    lines: &[&str],
+    mode: ParseMode,
+    warnings: &mut Vec<SourceWarning>,
+) -> Result<Legend, SourceError> {
+    let mut legend = Legend::default();
+
+    for (line_i, line) in lines.iter().enumerate() {
+        let line_number = first_line + line_i;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut split = line.split_ascii_whitespace();
+        let (char_, tile_id) = match (split.next(), split.next()) {
+            (Some(char_), Some(tile_id)) if char_.chars().count() == 1 => {
+                (char_.chars().next().expect("Char is not empty"), tile_id)
+            }
+            _ => {
+                return Err(SourceError::new(
+                    Position::new(line_number, 1),
+                    format!("Expected 'CHAR TILE_ID' legend line, got '{}'", line),
+                ))
+            }
+        };
+        if split.next().is_some() {
+            tolerate(
+                mode,
+                warnings,
+                SourceError::new(
+                    Position::new(line_number, 1),
+                    format!("Expected 'CHAR TILE_ID' legend line, got '{}'", line),
+                ),
+                "extra tokens are ignored",
+            )?;
+        }
+
+        let default = tile_id.ends_with(DEFAULT_TILE_SUFFIX);
+        let tile_id = tile_id.trim_end_matches(DEFAULT_TILE_SUFFIX);
+        if legend.tile_id(char_).is_some() {
+            tolerate(
+                mode,
+                warnings,
+                SourceError::new(
+                    Position::new(line_number, 1),
+                    format!("Legend char '{}' is defined twice", char_),
+                ),
+                "last one is used",
+            )?;
+            legend.entries.retain(|entry| entry.char_ != char_);
+        }
+        if default && legend.default_entry().is_some() {
+            tolerate(
+                mode,
+                warnings,
+                SourceError::new(
+                    Position::new(line_number, line.chars().count()),
+                    "Legend default tile is defined twice".to_string(),
+                ),
+                "last one is used",
+            )?;
+            for entry in legend.entries.iter_mut() {
+                entry.default = false;
+            }
+        }
+
+        legend.entries.push(LegendEntry {
+            char_,
+            tile_id: tile_id.to_string(),
+            default,
+        });
+    }
+
+    Ok(legend)
+}
+
+fn parse_geo(header_line: usize, lines: &[&str]) -> Result<Geo, SourceError> {
+    // Trailing empty lines are not part of grid
+    let height = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map(|last| last + 1)
+        .unwrap_or(0);
+    if height == 0 {
+        return Err(SourceError::new(
+            Position::new(header_line, 1),
+            format!("Block {} is empty", BLOCK_GEO),
+        ));
+    }
+
+    // Trailing whitespaces are not part of grid
+    let rows: Vec<Vec<char>> = lines[..height]
+        .iter()
+        .map(|line| line.trim_end().chars().collect())
+        .collect();
+    let row_lengths = rows.iter().map(|row| row.len()).collect();
+
+    Ok(Geo {
+        first_line: header_line + 1,
+        grid: Grid::new(rows, GEO_PADDING),
+        row_lengths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    const SOURCE: &str = "::META
+NAME: Test
+::LEGEND
+~ SEA*
+. PLAIN
+::GEO
+~~~
+~.~
+::NOTES
+Some notes
+";
+
+    fn parse_error(source: &str, mode: ParseMode) -> SourceError {
+        Document::parse(source, mode).expect_err("Source must be refused")
+    }
+
+    #[test]
+    fn parse_blocks() {
+        let (document, warnings) = Document::parse(SOURCE, ParseMode::Strict).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(document.blocks.len(), 4);
+        assert_eq!(document.meta().unwrap()[0].value, "Test");
+        assert_eq!(document.legend().unwrap().tile_id('.'), Some("PLAIN"));
+        assert_eq!(
+            document.legend().unwrap().default_entry().unwrap().char_,
+            '~'
+        );
+        assert_eq!(document.geo().unwrap().width(), 3);
+        assert_eq!(document.geo().unwrap().height(), 2);
+        assert_eq!(
+            document.blocks[3].content,
+            BlockContent::Raw(vec!["Some notes".to_string()])
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let (document, _) = Document::parse(SOURCE, ParseMode::Strict).unwrap();
+        let formatted = format!("{}", document);
+
+        assert_eq!(formatted, SOURCE);
+        let (reparsed, _) = Document::parse(&formatted, ParseMode::Strict).unwrap();
+        assert_eq!(reparsed, document);
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = parse_error("::META\nNAME Test\n", ParseMode::Lenient);
+        assert_eq!(error.position, Position::new(2, 10));
+
+        let error = parse_error("::LEGEND\n~ SEA\nSEA\n", ParseMode::Lenient);
+        assert_eq!(error.position, Position::new(3, 1));
+
+        let error = parse_error("::GEO\n\n", ParseMode::Lenient);
+        assert_eq!(error.position, Position::new(1, 1));
+        assert_eq!(format!("{}", error), "line 1, column 1: Block GEO is empty");
+    }
+
+    #[test]
+    fn strict_refuse_what_lenient_tolerate() {
+        let cases = [
+            ("content\n::GEO\n~\n", Position::new(1, 1)),
+            ("::GEO\n~\n::GEO\n.\n", Position::new(3, 1)),
+            ("::LEGEND\n~ SEA extra\n", Position::new(2, 1)),
+            ("::LEGEND\n~ SEA\n~ PLAIN\n", Position::new(3, 1)),
+            ("::LEGEND\n~ SEA*\n. PLAIN*\n", Position::new(3, 8)),
+        ];
+
+        for (source, position) in cases.iter() {
+            assert_eq!(parse_error(source, ParseMode::Strict).position, *position);
+            let (_, warnings) = Document::parse(source, ParseMode::Lenient).unwrap();
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].position, *position);
+        }
+    }
+
+    #[test]
+    fn lenient_resolutions() {
+        let (document, _) = Document::parse("::GEO\n~\n::GEO\n.\n", ParseMode::Lenient).unwrap();
+        assert_eq!(document.geo().unwrap().grid.rows().next(), Some(&['~'][..]));
+
+        let (document, _) =
+            Document::parse("::LEGEND\n~ SEA*\n~ PLAIN\n. ROCK*\n", ParseMode::Lenient).unwrap();
+        let legend = document.legend().unwrap();
+        assert_eq!(legend.tile_id('~'), Some("PLAIN"));
+        assert_eq!(legend.default_entry().unwrap().char_, '.');
+        assert_eq!(legend.entries.len(), 2);
+    }
+
+    #[test]
+    fn geo_rows_are_padded() {
+        let (document, _) = Document::parse("::GEO\n~~~  \n~\n", ParseMode::Strict).unwrap();
+        let geo = document.geo().unwrap();
+
+        assert_eq!(geo.width(), 3);
+        assert_eq!(geo.grid.get((1, 2)), Some(&GEO_PADDING));
+        assert!(geo.in_source((1, 0)));
+        assert!(!geo.in_source((1, 1)));
+        assert_eq!(format!("{}", document), "::GEO\n~~~\n~\n");
+    }
+
+    #[test]
+    fn char_warnings_aggregate_by_char() {
+        let mut char_warnings = CharWarnings::default();
+        char_warnings.add('x', Position::new(2, 3));
+        char_warnings.add('y', Position::new(2, 4));
+        char_warnings.add('x', Position::new(5, 1));
+
+        let warnings = char_warnings.into_warnings(|char_, count| format!("{} {}", char_, count));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].position, Position::new(2, 3));
+        assert_eq!(warnings[0].message, "x 2");
+        assert_eq!(warnings[1].position, Position::new(2, 4));
+        assert_eq!(warnings[1].message, "y 1");
+    }
+
+    #[test]
+    fn error_keep_source_position() {
+        let source_error = parse_error("::GEO\n\n", ParseMode::Strict);
+        let error: Error = source_error.into();
+
+        let source = std::error::Error::source(&error)
+            .and_then(|source| source.downcast_ref::<SourceError>())
+            .expect("Source error must be kept");
+        assert_eq!(source.position, Position::new(1, 1));
+    }
+}
@@ -1,4 +1,33 @@
-pub mod world;
-pub mod zone;
-
-pub type TileId = String;
+use std::str::FromStr;
+
+use serde_derive::{Deserialize, Serialize};
+
+pub mod world;
+pub mod zone;
+
+pub type TileId = String;
+
+/// How to handle source chars which are not in legend (or tiles)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParseMode {
+    /// Use default tile (unknown tile for zones) and collect a warning
+    Lenient,
+    /// Fail on first unknown char
+    Strict,
+}
+
+impl FromStr for ParseMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "lenient" => Ok(ParseMode::Lenient),
+            "strict" => Ok(ParseMode::Strict),
+            _ => Err(format!(
+                "Unknown parse mode '{}', expected 'lenient' or 'strict'",
+                value
+            )),
+        }
+    }
+}
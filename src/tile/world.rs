@@ -1,4 +1,4 @@
-use crate::error;
+use crate::source::Legend;
 use crate::tile::TileId;
 use std::collections::HashMap;
 
@@ -9,55 +9,40 @@ pub struct WorldTiles {
 }
 
 impl WorldTiles {
-    pub fn new(legend: &str) -> Result<Self, error::Error> {
-        let mut default_tile_id: Option<TileId> = None;
+    pub fn new(legend: &Legend) -> Self {
+        let codes: HashMap<u16, TileId> = legend
+            .entries
+            .iter()
+            .map(|entry| (entry.char_ as u16, entry.tile_id.clone()))
+            .collect();
 
-        let mut codes: HashMap<u16, TileId> = HashMap::new();
-
-        for line in legend.lines() {
-            let mut split = line.split_ascii_whitespace();
-            let char_ = split
-                .next()
-                .ok_or(error::Error::new(format!(
-                    "Unable to split tile line '{}'",
-                    line
-                )))?
-                .trim()
-                .chars()
-                .nth(0)
-                .ok_or(error::Error::new(format!(
-                    "Unable to read char from line '{}'",
-                    line
-                )))? as u16;
-            let mut id = split
-                .next()
-                .ok_or(error::Error::new(format!(
-                    "Unable to read second tile part from line '{}'",
-                    line
-                )))?
-                .trim();
-            if id.ends_with("*") {
-                id = id.trim_end_matches("*");
-                default_tile_id = Some(id.to_string());
-            }
-
-            codes.insert(char_, id.to_string());
-        }
-
-        Ok(WorldTiles {
+        WorldTiles {
             codes,
-            default: default_tile_id,
-        })
+            default: legend.default_entry().map(|entry| entry.tile_id.clone()),
+        }
     }
 
-    pub fn tile_id(&self, code: u16) -> Result<String, error::Error> {
-        Ok(self
+    /// Legend entries (char, tile id) sorted by tile id
+    pub fn legend(&self) -> Vec<(char, TileId)> {
+        let mut legend: Vec<(char, TileId)> = self
             .codes
-            .get(&code)
-            .ok_or(error::Error::new(format!(
-                "Unable to find tile_id for code '{}'",
-                code
-            )))?
-            .clone())
+            .iter()
+            .filter_map(|(code, tile_id)| {
+                std::char::from_u32(*code as u32).map(|char_| (char_, tile_id.clone()))
+            })
+            .collect();
+        legend.sort_by(|(_, a), (_, b)| a.cmp(b));
+        legend
+    }
+
+    pub fn char(&self, tile_id: &str) -> Option<char> {
+        self.codes
+            .iter()
+            .find(|(_, code_tile_id)| code_tile_id.as_str() == tile_id)
+            .and_then(|(code, _)| std::char::from_u32(*code as u32))
+    }
+
+    pub fn tile_id(&self, code: u16) -> Option<&TileId> {
+        self.codes.get(&code)
     }
 }
@@ -4,66 +4,187 @@ use std::collections::HashMap;
 use crate::error;
 use crate::tile::TileId;
 
-#[derive(Debug)]
-pub struct ZoneTiles {
-    codes: HashMap<u16, TileId>,
-    browseables: HashMap<TileId, bool>,
+/// Interned zone tile id: index in `ZoneTiles` tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZoneTileId(u16);
+
+/// Tile kinds animated corpses look for, from tiles data boolean properties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileCategory {
+    /// `"food": true`, like grass
+    Food,
+    /// `"water": true`, like fresh water
+    Water,
+}
+
+impl TileCategory {
+    pub const ALL: [TileCategory; 2] = [TileCategory::Food, TileCategory::Water];
+
+    fn property(&self) -> &str {
+        match self {
+            TileCategory::Food => "food",
+            TileCategory::Water => "water",
+        }
+    }
 }
 
-pub const NOTHING: &str = "NOTHING";
 pub const UNKNOWN: &str = "UNKNOWN";
+const UNKNOWN_TILE: ZoneTileId = ZoneTileId(0);
+
+#[derive(Debug)]
+pub struct ZoneTiles {
+    codes: HashMap<u16, ZoneTileId>,
+    // Indexed by ZoneTileId
+    ids: Vec<TileId>,
+    chars: Vec<Option<char>>,
+    browseables: Vec<bool>,
+    categories: Vec<Vec<TileCategory>>,
+}
 
 impl ZoneTiles {
     pub fn new(data: Value) -> Result<Self, error::Error> {
-        let mut codes = HashMap::new();
-        let mut browseables = HashMap::new();
-
-        for tile_value in data.as_array().ok_or(error::Error::new(format!(
-            "Unable to parse ZoneTiles array from '{}'",
-            data
-        )))? {
-            let tile_id: &str = tile_value["id"].as_str().ok_or(error::Error::new(format!(
-                "Unable to find tile id in '{}'",
-                tile_value
-            )))?;
-            let char: u16 = tile_value["char"]
+        let mut zone_tiles = ZoneTiles {
+            codes: HashMap::new(),
+            ids: vec![UNKNOWN.to_string()],
+            chars: vec![None],
+            browseables: vec![false],
+            categories: vec![vec![]],
+        };
+
+        for tile_value in data.as_array().ok_or(error::Error::invalid_source(
+            format!("Unable to parse ZoneTiles array from '{}'", data),
+            None,
+        ))? {
+            let tile_id: &str = tile_value["id"]
                 .as_str()
-                .ok_or(error::Error::new(format!(
-                    "Unable to find tile char in '{}'",
-                    tile_value
-                )))?
+                .ok_or(error::Error::invalid_source(
+                    format!("Unable to find tile id in '{}'", tile_value),
+                    None,
+                ))?;
+            let tile_char: char = tile_value["char"]
+                .as_str()
+                .ok_or(error::Error::invalid_source(
+                    format!("Unable to find tile char in '{}'", tile_value),
+                    None,
+                ))?
                 .chars()
                 .nth(0)
-                .ok_or(error::Error::new(format!(
-                    "Unable to find tile id in '{}'",
-                    tile_value
-                )))? as u16;
-
-            codes.insert(char, tile_id.to_string());
+                .ok_or(error::Error::invalid_source(
+                    format!("Unable to find tile id in '{}'", tile_value),
+                    None,
+                ))?;
             // TODO evolve browseables schema (WALKING, etc)
-            if let Some(traversable) = tile_value["traversable"].as_object() {
-                if let Some(walking) = traversable.get("WALKING") {
-                    if let Some(can_walk) = walking.as_bool() {
-                        browseables.insert(tile_id.to_string(), can_walk);
-                    }
-                }
+            let browseable = tile_value["traversable"]["WALKING"]
+                .as_bool()
+                .unwrap_or(false);
+            let categories: Vec<TileCategory> = TileCategory::ALL
+                .iter()
+                .filter(|category| tile_value[category.property()].as_bool().unwrap_or(false))
+                .copied()
+                .collect();
+
+            if zone_tiles.ids.len() > u16::MAX as usize {
+                return Err(error::Error::invalid_source(
+                    format!("Too many zone tiles (more than {})", u16::MAX),
+                    None,
+                ));
             }
+            let zone_tile_id = ZoneTileId(zone_tiles.ids.len() as u16);
+            zone_tiles.ids.push(tile_id.to_string());
+            zone_tiles.chars.push(Some(tile_char));
+            zone_tiles.browseables.push(browseable);
+            zone_tiles.categories.push(categories);
+            zone_tiles.codes.insert(tile_char as u16, zone_tile_id);
         }
 
-        Ok(ZoneTiles { codes, browseables })
+        Ok(zone_tiles)
     }
 
-    pub fn tile_id(&self, code: u16) -> String {
-        if let Some(tile_id) = self.codes.get(&code) {
-            return String::from(tile_id);
-        }
-        String::from(UNKNOWN)
+    pub fn unknown(&self) -> ZoneTileId {
+        UNKNOWN_TILE
     }
 
-    pub fn browseable(&self, tile_id: &str) -> bool {
-        if let Some(browseable) = self.browseables.get(tile_id) {
-            return *browseable;
-        }
-        false
+    /// Tile of given source char code
+    pub fn tile_id(&self, code: u16) -> Option<ZoneTileId> {
+        self.codes.get(&code).copied()
+    }
+
+    pub fn char(&self, zone_tile_id: ZoneTileId) -> Option<char> {
+        self.chars[zone_tile_id.0 as usize]
+    }
+
+    pub fn browseable(&self, zone_tile_id: ZoneTileId) -> bool {
+        self.browseables[zone_tile_id.0 as usize]
+    }
+
+    pub fn is(&self, zone_tile_id: ZoneTileId, category: TileCategory) -> bool {
+        self.categories[zone_tile_id.0 as usize].contains(&category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tiles() -> ZoneTiles {
+        ZoneTiles::new(json!([
+            {"id": "GRASS", "char": ".", "traversable": {"WALKING": true}, "food": true},
+            {"id": "FRESH_WATER", "char": "~", "traversable": {"WALKING": false}, "water": true},
+            {"id": "ROCK", "char": "#"},
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn tile_ids_are_interned() {
+        let tiles = tiles();
+
+        assert_eq!(tiles.ids, vec!["UNKNOWN", "GRASS", "FRESH_WATER", "ROCK"]);
+        assert_eq!(tiles.tile_id('.' as u16), Some(ZoneTileId(1)));
+        assert_eq!(tiles.tile_id('~' as u16), Some(ZoneTileId(2)));
+        assert_eq!(tiles.tile_id('#' as u16), Some(ZoneTileId(3)));
+        assert_eq!(tiles.char(ZoneTileId(2)), Some('~'));
+    }
+
+    #[test]
+    fn browseables_and_categories() {
+        let tiles = tiles();
+        let grass = tiles.tile_id('.' as u16).unwrap();
+        let water = tiles.tile_id('~' as u16).unwrap();
+        let rock = tiles.tile_id('#' as u16).unwrap();
+
+        assert!(tiles.browseable(grass));
+        assert!(!tiles.browseable(water));
+        assert!(!tiles.browseable(rock));
+        assert!(tiles.is(grass, TileCategory::Food));
+        assert!(!tiles.is(grass, TileCategory::Water));
+        assert!(tiles.is(water, TileCategory::Water));
+        assert!(!tiles.is(water, TileCategory::Food));
+        assert!(TileCategory::ALL
+            .iter()
+            .all(|category| !tiles.is(rock, *category)));
+    }
+
+    #[test]
+    fn unmapped_chars_are_unknown() {
+        let tiles = tiles();
+        let unknown = tiles.unknown();
+
+        assert_eq!(tiles.tile_id('x' as u16), None);
+        assert_eq!(tiles.ids[unknown.0 as usize], UNKNOWN);
+        assert_eq!(tiles.char(unknown), None);
+        assert!(!tiles.browseable(unknown));
+        assert!(TileCategory::ALL
+            .iter()
+            .all(|category| !tiles.is(unknown, *category)));
+    }
+
+    #[test]
+    fn invalid_tiles_data() {
+        assert!(ZoneTiles::new(json!({"id": "GRASS"})).is_err());
+        assert!(ZoneTiles::new(json!([{"char": "."}])).is_err());
+        assert!(ZoneTiles::new(json!([{"id": "GRASS"}])).is_err());
+        assert!(ZoneTiles::new(json!([{"id": "GRASS", "char": ""}])).is_err());
     }
 }
@@ -1,48 +1,20 @@
-use crate::error;
-
-pub const BLOCK_GEO: &str = "GEO";
-
-pub fn extract_block_from_source(block_name: &str, source: &str) -> Result<String, error::Error> {
-    let mut block_found = false;
-    let mut block_lines: Vec<&str> = Vec::new();
-
-    for line in source.lines() {
-        if line.starts_with("::") {
-            // TODO BS 2019-04-03: there is strip method ?
-            let line_block_name = line.replace("::", "").replace("\n", "").replace(" ", "");
-            if line_block_name == block_name {
-                block_found = true;
-            } else if block_found {
-                return Ok(block_lines.join("\n"));
-            }
-        } else if block_found {
-            block_lines.push(line);
-        }
-    }
-
-    if block_found {
-        return Ok(block_lines.join("\n"));
-    }
-    Err(error::Error::new(format!(
-        "Block \"{}\" not found",
-        block_name
-    )))
-}
-
-pub fn longest_line(text: &str) -> Option<&str> {
-    let mut max_length = 0;
-    let mut longest_line: Option<&str> = None;
-
-    for line in text.lines() {
-        let contents = line.trim_end();
-        let line_length = contents.len();
-        if line_length > max_length {
-            max_length = line_length;
-            longest_line = Some(contents);
-        }
+/// Parse zone coordinates written as "ROW.COL" (like in logs)
+pub fn parse_zone_coordinates(value: &str) -> Result<(u32, u32), String> {
+    let mut split = value.split('.');
+    match (split.next(), split.next(), split.next()) {
+        (Some(row_i), Some(col_i), None) => Ok((
+            row_i
+                .parse()
+                .map_err(|err| format!("Invalid zone row '{}': {}", row_i, err))?,
+            col_i
+                .parse()
+                .map_err(|err| format!("Invalid zone col '{}': {}", col_i, err))?,
+        )),
+        _ => Err(format!(
+            "Invalid zone coordinates '{}', expected ROW.COL",
+            value
+        )),
     }
-
-    longest_line
 }
 
 #[derive(Debug)]
@@ -122,6 +94,13 @@ pub fn position_direction_from(reference: (u32, u32), position: (u32, u32)) -> O
     }
 }
 
+/// Number of moves (diagonals included) between positions
+pub fn distance(position1: (u32, u32), position2: (u32, u32)) -> u32 {
+    let row_distance = (position1.0 as i32 - position2.0 as i32).unsigned_abs();
+    let col_distance = (position1.1 as i32 - position2.1 as i32).unsigned_abs();
+    row_distance.max(col_distance)
+}
+
 pub fn is_near(position1: (u32, u32), position2: (u32, u32), distance: u32) -> bool {
     let row_distance = (position1.0 as i32 - position2.0 as i32).abs() as u32;
     let col_distance = (position1.1 as i32 - position2.1 as i32).abs() as u32;
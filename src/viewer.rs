@@ -0,0 +1,168 @@
+use std::io::Write;
+
+use async_std::channel::Receiver;
+use async_std::sync::Mutex;
+
+use crate::message::ZoneCoordinates;
+use crate::registry::Zones;
+use crate::zone::Zone;
+
+const UNKNOWN_GLYPH: char = '?';
+const BUILD_GLYPH: char = '#';
+const CHARACTER_GLYPH: char = '@';
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
+fn put(lines: &mut [Vec<char>], row_i: u32, col_i: u32, glyph: char) {
+    if let Some(line) = lines.get_mut(row_i as usize) {
+        if let Some(cell) = line.get_mut(col_i as usize) {
+            *cell = glyph;
+        }
+    }
+}
+
+/// Render zone tiles with original tile chars, overlaid by builds, animated corpses and
+/// characters (in this order, so characters are always visible).
+pub fn render(zone: &Zone) -> String {
+    let mut lines: Vec<Vec<char>> = zone
+        .grid
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(|zone_tile_id| zone.tiles.char(*zone_tile_id).unwrap_or(UNKNOWN_GLYPH))
+                .collect()
+        })
+        .collect();
+
+    for build in &zone.builds {
+        put(&mut lines, build.row_i, build.col_i, BUILD_GLYPH);
+    }
+    for animated_corpse in &zone.animated_corpses {
+        put(
+            &mut lines,
+            animated_corpse.zone_row_i(),
+            animated_corpse.zone_col_i(),
+            animated_corpse.type_().glyph(),
+        );
+    }
+    for character in &zone.characters {
+        put(
+            &mut lines,
+            character.zone_row_i,
+            character.zone_col_i,
+            CHARACTER_GLYPH,
+        );
+    }
+
+    let mut output = format!(
+        "Zone {}.{}: {} animated corpses, {} characters, {} builds\n",
+        zone.world_row_i,
+        zone.world_col_i,
+        zone.animated_corpses.len(),
+        zone.characters.len(),
+        zone.builds.len(),
+    );
+    for line in lines {
+        output.push_str(&line.into_iter().collect::<String>());
+        output.push('\n');
+    }
+    output
+}
+
+fn draw(zone: &Zone) {
+    let mut stdout = std::io::stdout();
+    if let Err(err) =
+        write!(stdout, "{}{}", CLEAR_SCREEN, render(zone)).and_then(|_| stdout.flush())
+    {
+        log::error!(
+            "Fail to draw zone {}.{}: {}",
+            zone.world_row_i,
+            zone.world_col_i,
+            err
+        );
+    }
+}
+
+pub async fn view(
+    zones: &Mutex<Zones>,
+    updated_receiver: Receiver<ZoneCoordinates>,
+    coordinates: ZoneCoordinates,
+) {
+    log::info!("View zone {}.{}", coordinates.0, coordinates.1);
+
+    loop {
+        {
+            let zones = zones.lock().await;
+            match zones.get(coordinates) {
+                Some(zone) => draw(zone),
+                None => println!(
+                    "{}Zone {}.{} is not loaded ({} hits)",
+                    CLEAR_SCREEN,
+                    coordinates.0,
+                    coordinates.1,
+                    zones.missing_zone_hits(coordinates)
+                ),
+            }
+        }
+
+        // Wait until a message is applied on the viewed zone
+        loop {
+            match updated_receiver.recv().await {
+                Ok(updated) if updated == coordinates => break,
+                Ok(_) => continue,
+                Err(_) => {
+                    log::error!("Viewer channel is closed");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ac::Type;
+    use crate::model::{Build, Character};
+    use crate::zone::tests::{animated_corpse, zone};
+
+    fn build(row_i: u32, col_i: u32) -> Build {
+        Build {
+            id: 1,
+            build_id: "WALL".to_string(),
+            row_i,
+            col_i,
+            traversable: Default::default(),
+        }
+    }
+
+    fn character(row_i: u32, col_i: u32) -> Character {
+        Character {
+            id: "abc".to_string(),
+            zone_row_i: row_i,
+            zone_col_i: col_i,
+        }
+    }
+
+    #[test]
+    fn render_overlays() {
+        let mut zone = zone(
+            "....\n.~\n",
+            vec![
+                animated_corpse(1, Type::GOAT, (0, 1)),
+                animated_corpse(2, Type::GOAT, (0, 2)),
+            ],
+        );
+        zone.builds = vec![build(0, 0), build(0, 1), build(9, 9)];
+        zone.characters = vec![character(0, 2), character(1, 9)];
+
+        let rendered = render(&zone);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "Zone 0.0: 2 animated corpses, 2 characters, 3 builds"
+        );
+        // Characters over animated corpses over builds, padding cells are unknown tiles
+        assert_eq!(&lines[1..], &["#g@.", ".~??"]);
+    }
+}
@@ -1,72 +1,112 @@
 use crate::client::Client;
 use crate::error;
+use crate::grid::Grid;
+use crate::source::{CharWarnings, Document, Geo, SourceError, SourceWarning};
 use crate::tile::world::WorldTiles;
-use crate::util;
-
-#[derive(Debug, Clone)]
-pub struct WorldRow {
-    pub cols: Vec<String>,
-}
+use crate::tile::{ParseMode, TileId};
 
 #[derive(Clone)]
 pub struct World {
-    pub width: i32,
-    pub height: i32,
-    pub rows: Vec<WorldRow>,
+    pub grid: Grid<TileId>,
+    pub tiles: WorldTiles,
 }
 
 impl World {
-    pub fn new(world_raw: &str, tiles: &WorldTiles) -> Result<Self, error::Error> {
-        let height = world_raw.lines().count() as i32;
-        let longest_line = if let Some(longest_line) = util::longest_line(world_raw) {
-            longest_line
-        } else {
-            return Err(error::Error::new("World raw seem to be empty".to_string()));
-        };
+    /// Build world from its GEO grid. Return also warnings about unknown chars replaced by
+    /// default tile (in lenient mode). Padding cells are default tile, or an empty tile id if
+    /// legend has no default tile.
+    pub fn new(
+        geo: &Geo,
+        tiles: WorldTiles,
+        mode: ParseMode,
+    ) -> Result<(Self, Vec<SourceWarning>), error::Error> {
+        let mut unknown_chars = CharWarnings::default();
 
-        let width = longest_line.chars().count() as i32;
-        let mut rows: Vec<WorldRow> = Vec::new();
+        let grid = geo.grid.try_map(|position, tile_char| {
+            if !geo.in_source(position) {
+                return Ok(tiles.default.clone().unwrap_or_default());
+            }
+            match (tiles.tile_id(*tile_char as u16), mode, &tiles.default) {
+                (Some(tile_id), _, _) => Ok(tile_id.clone()),
+                (None, ParseMode::Lenient, Some(default)) => {
+                    unknown_chars.add(*tile_char, geo.position(position));
+                    Ok(default.clone())
+                }
+                (None, ParseMode::Lenient, None) => Err(SourceError::new(
+                    geo.position(position),
+                    format!(
+                        "Char '{}' is not in legend and legend has no default tile",
+                        tile_char
+                    ),
+                )),
+                (None, ParseMode::Strict, _) => Err(SourceError::new(
+                    geo.position(position),
+                    format!("Char '{}' is not in legend", tile_char),
+                )),
+            }
+        })?;
+        let default = tiles.default.clone().unwrap_or_default();
+        let warnings = unknown_chars.into_warnings(|tile_char, count| {
+            format!(
+                "Char '{}' is not in legend ({} cells), use default tile {}",
+                tile_char, count, default
+            )
+        });
 
-        for line in world_raw.lines() {
-            let mut cols: Vec<String> = Vec::new();
+        Ok((Self { grid, tiles }, warnings))
+    }
+}
 
-            for tile_char in line.chars() {
-                let tile_id = tiles.tile_id(tile_char as u16)?;
-                cols.push(tile_id);
-            }
+pub fn new(client: &Client, mode: ParseMode) -> Result<World, error::Error> {
+    let world_source = client.get_world_source()?;
+    let (document, source_warnings) = Document::parse(&world_source, mode)?;
+    let world_tiles = WorldTiles::new(document.require_legend()?);
+    let (world, world_warnings) = World::new(document.require_geo()?, world_tiles, mode)?;
+    for warning in source_warnings.iter().chain(world_warnings.iter()) {
+        log::warn!("World source: {}", warning);
+    }
+    Ok(world)
+}
 
-            let world_row = WorldRow { cols };
-            rows.push(world_row);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(Self {
-            width,
-            height,
-            rows,
-        })
+    fn world(source: &str, mode: ParseMode) -> Result<(World, Vec<SourceWarning>), error::Error> {
+        let (document, _) = Document::parse(source, mode)?;
+        let tiles = WorldTiles::new(document.require_legend()?);
+        World::new(document.require_geo()?, tiles, mode)
     }
 
-    pub fn _tile_id(&self, row_i: i32, col_i: i32) -> Option<String> {
-        if row_i < 0 || col_i < 0 {
-            return None;
-        }
+    #[test]
+    fn ragged_world_is_padded_with_default_tile() {
+        let source = "::LEGEND\n. PLAIN*\n~ SEA\n::GEO\n~\n.~.\n";
+        let (world, warnings) = world(source, ParseMode::Strict).unwrap();
 
-        if let Some(row) = self.rows.get(row_i as usize) {
-            if col_i as usize >= row.cols.len() {
-                return None;
-            }
+        assert!(warnings.is_empty());
+        assert_eq!(world.grid.get((0, 0)).unwrap(), "SEA");
+        assert_eq!(world.grid.get((0, 2)).unwrap(), "PLAIN");
+    }
 
-            return Some(row.cols[col_i as usize].clone());
-        }
+    #[test]
+    fn ragged_world_without_default_tile() {
+        let source = "::LEGEND\n. PLAIN\n~ SEA\n::GEO\n~\n.~.\n";
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let (world, warnings) = world(source, mode).unwrap();
 
-        None
+            assert!(warnings.is_empty());
+            assert_eq!(world.grid.width(), 3);
+            assert_eq!(world.grid.get((0, 0)).unwrap(), "SEA");
+            assert_eq!(world.grid.get((0, 1)).unwrap(), "");
+            assert_eq!(world.grid.get((0, 2)).unwrap(), "");
+            assert_eq!(world.grid.get((1, 2)).unwrap(), "PLAIN");
+        }
     }
-}
 
-pub fn new(client: &Client) -> Result<World, error::Error> {
-    let world_source = client.get_world_source()?;
-    let legend = util::extract_block_from_source("LEGEND", world_source.as_str())?;
-    let world_raw = util::extract_block_from_source("GEO", world_source.as_str())?;
-    let world_tiles = WorldTiles::new(legend.as_str())?;
-    Ok(World::new(world_raw.as_str(), &world_tiles)?)
+    #[test]
+    fn unknown_char_without_default_tile() {
+        let source = "::LEGEND\n. PLAIN\n::GEO\n.x\n";
+        assert!(world(source, ParseMode::Strict).is_err());
+        assert!(world(source, ParseMode::Lenient).is_err());
+    }
 }
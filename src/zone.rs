@@ -1,19 +1,23 @@
+use crate::ac::pending::PendingMoves;
 use crate::ac::AnimatedCorpse;
+use crate::behavior::flock::FLOCK_RADIUS;
 use crate::behavior::get_behaviors_for;
-use crate::client::{Client};
+use crate::client::Client;
+use crate::clock::GameTime;
 use crate::error;
 use crate::event::ZoneEvent;
-use crate::message::{Message, ZoneMessage};
+use crate::grid::{Grid, GridPosition};
+use crate::message::{
+    Alarm, AnimatedCorpseId, Message, SendEventMessage, ZoneCoordinates, ZoneMessage,
+};
 use crate::model::Character;
-use crate::tile::zone::{ZoneTiles, NOTHING};
-use crate::tile::TileId;
+use crate::occupancy::Occupancy;
+use crate::source::{CharWarnings, Document, Geo, SourceError, SourceWarning};
+use crate::tile::zone::{TileCategory, ZoneTileId, ZoneTiles, UNKNOWN};
+use crate::tile::ParseMode;
 use crate::world::World;
 use crate::{ac, model, util};
-
-#[derive(Debug)]
-pub struct LevelRow {
-    pub cols: Vec<String>,
-}
+use std::collections::{HashMap, HashSet};
 
 pub struct Zone {
     pub world_row_i: u32,
@@ -21,67 +25,93 @@ pub struct Zone {
     pub animated_corpses: Vec<Box<dyn ac::AnimatedCorpse + Send + Sync>>,
     pub characters: Vec<model::Character>,
     pub builds: Vec<model::Build>,
-    pub width: i32,
-    pub height: i32,
-    pub rows: Vec<LevelRow>,
-    pub world_tile_type_id: TileId,
+    pub grid: Grid<ZoneTileId>,
     pub tiles: ZoneTiles,
-    pub client: Client,
+    /// Positions of categorized tiles, to not browse whole grid when looking for them
+    pub category_positions: HashMap<TileCategory, Vec<GridPosition>>,
+    pub pending_moves: PendingMoves,
+    pub occupancy: Occupancy,
+    /// Leader of herd of each animated corpse (leaders included)
+    pub herd_leaders: HashMap<AnimatedCorpseId, AnimatedCorpseId>,
 }
 
 impl Zone {
+    /// Build zone from its GEO grid. Return also warnings about unknown chars replaced by
+    /// unknown tile (in lenient mode). Padding cells are unknown tile.
     pub fn new(
-        world_row_i: u32,
-        world_col_i: u32,
+        (world_row_i, world_col_i): ZoneCoordinates,
         animated_corpses: Vec<Box<dyn ac::AnimatedCorpse + Send + Sync>>,
         characters: Vec<model::Character>,
         builds: Vec<model::Build>,
-        zone_raw: &str,
+        geo: &Geo,
         tiles: ZoneTiles,
-        world_tile_type_id: String,
-        client: Client,
-    ) -> Result<Self, error::Error> {
-        let height = zone_raw.lines().count() as i32;
-        let longest_line = if let Some(longest_line) = util::longest_line(zone_raw) {
-            longest_line
-        } else {
-            return Err(error::Error::new("Zone raw seem to be empty".to_string()));
-        };
-
-        let width = longest_line.chars().count() as i32;
-        let mut rows: Vec<LevelRow> = Vec::new();
-
-        for line in zone_raw.lines() {
-            let mut cols: Vec<String> = Vec::new();
+        mode: ParseMode,
+    ) -> Result<(Self, Vec<SourceWarning>), error::Error> {
+        let mut unknown_chars = CharWarnings::default();
 
-            for tile_char in line.chars() {
-                let tile_id = tiles.tile_id(tile_char as u16);
-                cols.push(tile_id);
+        let grid = geo.grid.try_map(|position, tile_char| {
+            match (tiles.tile_id(*tile_char as u16), mode) {
+                _ if !geo.in_source(position) => Ok(tiles.unknown()),
+                (Some(zone_tile_id), _) => Ok(zone_tile_id),
+                (None, ParseMode::Lenient) => {
+                    unknown_chars.add(*tile_char, geo.position(position));
+                    Ok(tiles.unknown())
+                }
+                (None, ParseMode::Strict) => Err(SourceError::new(
+                    geo.position(position),
+                    format!("Char '{}' is not a known tile", tile_char),
+                )),
             }
+        })?;
+        let warnings = unknown_chars.into_warnings(|tile_char, count| {
+            format!(
+                "Char '{}' is not a known tile ({} cells), use {}",
+                tile_char, count, UNKNOWN
+            )
+        });
 
-            let level_row = LevelRow { cols };
-            rows.push(level_row);
+        let mut category_positions: HashMap<TileCategory, Vec<GridPosition>> = HashMap::new();
+        for (position, zone_tile_id) in grid.iter() {
+            for category in TileCategory::ALL.iter() {
+                if tiles.is(*zone_tile_id, *category) {
+                    category_positions
+                        .entry(*category)
+                        .or_default()
+                        .push(position);
+                }
+            }
         }
 
-        Ok(Zone {
+        let pending_moves = PendingMoves::new();
+        let occupancy = Occupancy::new(&animated_corpses, &characters, &pending_moves);
+
+        let mut zone = Zone {
             world_row_i,
             world_col_i,
             animated_corpses,
             characters,
             builds,
-            width,
-            height,
-            rows,
-            world_tile_type_id,
+            grid,
             tiles,
-            client,
-        })
+            category_positions,
+            pending_moves,
+            occupancy,
+            herd_leaders: HashMap::new(),
+        };
+        zone.elect_herd_leaders();
+
+        Ok((zone, warnings))
     }
 
     pub fn on_event(&mut self, event: &ZoneEvent) -> Vec<Message> {
         let mut messages: Vec<Message> = vec![];
 
         for animated_corpse in self.animated_corpses.iter() {
+            // Wait for server acknowledgement of pending move before moving it again
+            if self.pending_moves.contains(animated_corpse.id()) {
+                continue;
+            }
+
             for message_ in animated_corpse.on_event(event, self) {
                 messages.push(message_);
             }
@@ -96,10 +126,14 @@ impl Zone {
         messages
     }
 
-    pub fn animate(&self, tick_count: u64) -> Vec<Message> {
+    pub fn animate(&self, tick_count: u64, time: &GameTime) -> Vec<Message> {
         let mut messages: Vec<Message> = vec![];
 
         for animated_corpse in self.animated_corpses.iter() {
+            if self.pending_moves.contains(animated_corpse.id()) {
+                continue;
+            }
+
             for message_ in animated_corpse.animate(self, tick_count) {
                 messages.push(message_)
             }
@@ -107,7 +141,7 @@ impl Zone {
             for behavior in get_behaviors_for(animated_corpse).iter() {
                 if let Some(animate_each) = behavior.animate_each() {
                     if tick_count % animate_each as u64 == 0 {
-                        for message_ in behavior.on_animate(animated_corpse, self) {
+                        for message_ in behavior.on_animate(animated_corpse, self, time) {
                             messages.push(message_);
                         }
                     }
@@ -118,9 +152,208 @@ impl Zone {
         messages
     }
 
-    pub fn on_message(&mut self, message: ZoneMessage) {
+    /// Return false if given event message must not be sent to server. Only the first move
+    /// required for an animated corpse is kept until server confirms it: as behaviors are
+    /// animated in `get_behaviors_for` order, earlier behaviors moves take precedence.
+    pub fn accept_event_message(&mut self, message: &SendEventMessage) -> bool {
         match message {
-            ZoneMessage::UpdateAnimatedCorpsePosition(_, _, _) => {
+            SendEventMessage::RequireAnimatedCorpseMove(animated_corpse_id, to_row_i, to_col_i) => {
+                if self.pending_moves.contains(*animated_corpse_id) {
+                    log::debug!(
+                        "Animated corpse {} already have a pending move, ignore move to {}.{}",
+                        animated_corpse_id,
+                        to_row_i,
+                        to_col_i
+                    );
+                    return false;
+                }
+
+                // Destination can have been taken since move was decided
+                if let Some(animated_corpse) = self.animated_corpse(*animated_corpse_id) {
+                    if !self.occupancy.can_enter(
+                        *animated_corpse_id,
+                        animated_corpse.type_(),
+                        (*to_row_i, *to_col_i),
+                    ) {
+                        log::debug!(
+                            "Tile {}.{} is occupied, ignore move of animated corpse {}",
+                            to_row_i,
+                            to_col_i,
+                            animated_corpse_id
+                        );
+                        return false;
+                    }
+                }
+
+                self.pending_moves
+                    .register(*animated_corpse_id, *to_row_i, *to_col_i);
+                self.refresh_occupancy();
+                true
+            }
+            SendEventMessage::AnimatedCorpseEat(..) => true,
+        }
+    }
+
+    pub fn refresh_occupancy(&mut self) {
+        self.occupancy = Occupancy::new(
+            &self.animated_corpses,
+            &self.characters,
+            &self.pending_moves,
+        );
+    }
+
+    pub fn animated_corpse(
+        &self,
+        animated_corpse_id: u32,
+    ) -> Option<&(dyn ac::AnimatedCorpse + Send + Sync)> {
+        self.animated_corpses
+            .iter()
+            .find(|animated_corpse| animated_corpse.id() == animated_corpse_id)
+            .map(|animated_corpse| animated_corpse.as_ref())
+    }
+
+    /// Leader of given animated corpse herd, None if it is the leader itself
+    pub fn herd_leader(
+        &self,
+        animated_corpse: &(dyn ac::AnimatedCorpse + Send + Sync),
+    ) -> Option<&(dyn ac::AnimatedCorpse + Send + Sync)> {
+        self.herd_leaders
+            .get(&animated_corpse.id())
+            .filter(|leader_id| **leader_id != animated_corpse.id())
+            .and_then(|leader_id| self.animated_corpse(*leader_id))
+    }
+
+    /// Herds: animated corpses of same type linked by mates at FLOCK_RADIUS (or less) from
+    /// each other
+    fn herds(&self) -> Vec<Vec<&(dyn ac::AnimatedCorpse + Send + Sync)>> {
+        let mut in_herd = vec![false; self.animated_corpses.len()];
+        let mut herds = vec![];
+
+        for first_i in 0..self.animated_corpses.len() {
+            if in_herd[first_i] {
+                continue;
+            }
+            in_herd[first_i] = true;
+            let mut herd = vec![self.animated_corpses[first_i].as_ref()];
+
+            let mut member_i = 0;
+            while member_i < herd.len() {
+                let member = herd[member_i];
+                for (other_i, other) in self.animated_corpses.iter().enumerate() {
+                    if !in_herd[other_i]
+                        && other.type_() == member.type_()
+                        && util::is_near(
+                            (member.zone_row_i(), member.zone_col_i()),
+                            (other.zone_row_i(), other.zone_col_i()),
+                            FLOCK_RADIUS,
+                        )
+                    {
+                        in_herd[other_i] = true;
+                        herd.push(other.as_ref());
+                    }
+                }
+                member_i += 1;
+            }
+
+            herds.push(herd);
+        }
+
+        herds
+    }
+
+    /// Elect leader of each herd: its current leader (boldest one if herds merged), else its
+    /// boldest member (lowest id if equal), so leadership passes on when leader leaves herd
+    fn elect_herd_leaders(&mut self) {
+        let boldest = |candidate1: &&(dyn ac::AnimatedCorpse + Send + Sync),
+                       candidate2: &&(dyn ac::AnimatedCorpse + Send + Sync)| {
+            candidate1
+                .traits()
+                .boldness
+                .total_cmp(&candidate2.traits().boldness)
+                .then(candidate2.id().cmp(&candidate1.id()))
+        };
+        let leader_ids: HashSet<AnimatedCorpseId> = self.herd_leaders.values().copied().collect();
+        let mut herd_leaders = HashMap::new();
+
+        for herd in self.herds() {
+            let leader = herd
+                .iter()
+                .copied()
+                .filter(|member| leader_ids.contains(&member.id()))
+                .max_by(boldest)
+                .or_else(|| herd.iter().copied().max_by(boldest))
+                .expect("Herd is not empty");
+            if !leader_ids.contains(&leader.id()) {
+                log::debug!(
+                    "Zone {}.{}: animated corpse {} is now leader of a {:?} herd of {}",
+                    self.world_row_i,
+                    self.world_col_i,
+                    leader.id(),
+                    leader.type_(),
+                    herd.len()
+                );
+            }
+            for member in herd.iter() {
+                herd_leaders.insert(member.id(), leader.id());
+            }
+        }
+
+        self.herd_leaders = herd_leaders;
+    }
+
+    /// Apply message and return messages produced in reaction
+    pub fn on_message(&mut self, message: ZoneMessage) -> Vec<Message> {
+        if let ZoneMessage::Alarm(alarm) = message {
+            return self.on_alarm(&alarm);
+        }
+
+        // Occupancy only depends on who is where, herds on where animated corpses are
+        let (occupancy_changed, herds_changed) = match &message {
+            ZoneMessage::UpdateCharacterPosition(..)
+            | ZoneMessage::AddCharacter(..)
+            | ZoneMessage::RemoveCharacter(_) => (true, false),
+            ZoneMessage::UpdateAnimatedCorpsePosition(..)
+            | ZoneMessage::AddAnimatedCorpse(_)
+            | ZoneMessage::RemoveAnimatedCorpse(_) => (true, true),
+            _ => (false, false),
+        };
+
+        self.apply_message(message);
+        if herds_changed {
+            self.elect_herd_leaders();
+        }
+        if occupancy_changed {
+            self.refresh_occupancy();
+        }
+        vec![]
+    }
+
+    fn on_alarm(&self, alarm: &Alarm) -> Vec<Message> {
+        let mut messages: Vec<Message> = vec![];
+
+        for animated_corpse in self.animated_corpses.iter() {
+            // Already fleeing (or moving) ones don't react again
+            if self.pending_moves.contains(animated_corpse.id()) {
+                continue;
+            }
+
+            for behavior in get_behaviors_for(animated_corpse).iter() {
+                messages.extend(behavior.on_alarm(animated_corpse.as_ref(), alarm, self));
+            }
+        }
+
+        messages
+    }
+
+    fn apply_message(&mut self, message: ZoneMessage) {
+        match message {
+            ZoneMessage::UpdateAnimatedCorpsePosition(
+                animated_corpse_id,
+                zone_row_i,
+                zone_col_i,
+            ) => {
+                self.pending_moves
+                    .acknowledge(animated_corpse_id, zone_row_i, zone_col_i);
                 for animated_corpse in self.animated_corpses.iter_mut() {
                     match message {
                         ZoneMessage::UpdateAnimatedCorpsePosition(
@@ -129,6 +362,15 @@ impl Zone {
                             zone_col_id,
                         ) => {
                             if animated_corpse.id() == animated_corpse_id {
+                                let heading = (
+                                    (zone_row_id as i32 - animated_corpse.zone_row_i() as i32)
+                                        .signum() as i8,
+                                    (zone_col_id as i32 - animated_corpse.zone_col_i() as i32)
+                                        .signum() as i8,
+                                );
+                                if heading != (0, 0) {
+                                    animated_corpse.set_heading(heading);
+                                }
                                 animated_corpse.set_zone_row_i(zone_row_id);
                                 animated_corpse.set_zone_col_i(zone_col_id);
                             }
@@ -137,6 +379,41 @@ impl Zone {
                     }
                 }
             }
+            ZoneMessage::UpdateAnimatedCorpseHunger(animated_corpse_id, hunger) => {
+                for animated_corpse in self.animated_corpses.iter_mut() {
+                    if animated_corpse.id() == animated_corpse_id {
+                        animated_corpse.set_hunger(hunger);
+                    }
+                }
+            }
+            ZoneMessage::UpdateAnimatedCorpseThirst(animated_corpse_id, thirst) => {
+                for animated_corpse in self.animated_corpses.iter_mut() {
+                    if animated_corpse.id() == animated_corpse_id {
+                        animated_corpse.set_thirst(thirst);
+                    }
+                }
+            }
+            ZoneMessage::UpdateAnimatedCorpseLinger(animated_corpse_id, linger) => {
+                for animated_corpse in self.animated_corpses.iter_mut() {
+                    if animated_corpse.id() == animated_corpse_id {
+                        animated_corpse.set_linger(linger);
+                    }
+                }
+            }
+            ZoneMessage::UpdateAnimatedCorpseAsleep(animated_corpse_id, asleep) => {
+                for animated_corpse in self.animated_corpses.iter_mut() {
+                    if animated_corpse.id() == animated_corpse_id {
+                        animated_corpse.set_asleep(asleep);
+                    }
+                }
+            }
+            ZoneMessage::UpdateAnimatedCorpseEmotion(animated_corpse_id, emotion, cooldown) => {
+                for animated_corpse in self.animated_corpses.iter_mut() {
+                    if animated_corpse.id() == animated_corpse_id {
+                        animated_corpse.set_emotion(emotion, cooldown);
+                    }
+                }
+            }
             ZoneMessage::UpdateCharacterPosition(character_id, to_row_i, to_col_i) => {
                 for character in self.characters.iter_mut() {
                     if character.id == character_id {
@@ -164,98 +441,304 @@ impl Zone {
             ZoneMessage::AddBuild(build) => {
                 self.builds.push(build);
             }
-            ZoneMessage::AddAnimatedCorpse(animated_corpse_id) => {
-                match self.client.get_animated_corpse(animated_corpse_id) {
-                    Ok(animated_corpse) => {
-                        self.animated_corpses.push(animated_corpse)
-                    }
-                    Err(err) => {
-                        eprintln!("Fail to add animated corpse : {}", err)
-                    }
-                }
-
+            // Alarms don't change zone state, see on_alarm
+            ZoneMessage::Alarm(_) => {}
+            ZoneMessage::AddAnimatedCorpse(base) => {
+                self.remove_animated_corpse(base.id);
+                self.animated_corpses
+                    .push(ac::animated_corpse_from_base(base))
+            }
+            ZoneMessage::RemoveAnimatedCorpse(animated_corpse_id) => {
+                self.remove_animated_corpse(animated_corpse_id);
+                self.pending_moves.forget(animated_corpse_id);
             }
         }
     }
 
-    pub fn tile_id(&self, row_i: u32, col_i: u32) -> TileId {
-        if row_i >= self.rows.len() as u32 {
-            return String::from(NOTHING);
-        }
-
-        let row = &self.rows[row_i as usize];
+    fn remove_animated_corpse(&mut self, animated_corpse_id: AnimatedCorpseId) {
+        self.animated_corpses
+            .retain(|animated_corpse| animated_corpse.id() != animated_corpse_id);
+    }
 
-        if col_i >= row.cols.len() as u32 {
-            return String::from(NOTHING);
-        }
+    pub fn get_successors(&self, row_i: u32, col_i: u32) -> Vec<((u32, u32), u32)> {
+        self.grid
+            .neighbours((row_i, col_i))
+            .filter(|(_, zone_tile_id)| self.tiles.browseable(**zone_tile_id))
+            .map(|(position, _)| (position, 1))
+            .collect()
+    }
 
-        row.cols[col_i as usize].clone()
+    /// Free successor of animated corpse which is nearest to target, if nearer than its
+    /// current position
+    pub fn step_toward(
+        &self,
+        animated_corpse: &(dyn ac::AnimatedCorpse + Send + Sync),
+        target: GridPosition,
+    ) -> Option<GridPosition> {
+        let position = (animated_corpse.zone_row_i(), animated_corpse.zone_col_i());
+        self.get_free_successors(animated_corpse)
+            .into_iter()
+            .map(|(successor, _weight)| successor)
+            .min_by_key(|successor| util::distance(*successor, target))
+            .filter(|successor| {
+                util::distance(*successor, target) < util::distance(position, target)
+            })
     }
 
-    pub fn get_successors(&self, row_i: u32, col_i: u32) -> Vec<((u32, u32), u32)> {
-        let mut successors = vec![];
-        let row_i = row_i as i32;
-        let col_i = col_i as i32;
-
-        for (modifier_row_i, modifier_col_i) in [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            // no center pos
-            (0, 1),
-            (1, 1),
-            (1, -1),
-            (1, 0),
-        ]
-        .iter()
-        {
-            let new_row_i = row_i + *modifier_row_i;
-            let new_col_i = col_i + *modifier_col_i;
-
-            // Ignore outside coordinates
-            if new_row_i >= 0 && new_col_i >= 0 {
-                if self
-                    .tiles
-                    .browseable(&self.tile_id(new_row_i as u32, new_col_i as u32))
-                {
-                    successors.push(((new_row_i as u32, new_col_i as u32), 1));
-                }
-            }
-        }
+    /// Positions of given category tiles at given distance (at most) of position, nearest first
+    pub fn tiles_near(
+        &self,
+        category: TileCategory,
+        position: GridPosition,
+        distance: u32,
+    ) -> Vec<GridPosition> {
+        let mut positions: Vec<GridPosition> = self
+            .category_positions
+            .get(&category)
+            .map(|positions| {
+                positions
+                    .iter()
+                    .filter(|tile_position| util::is_near(position, **tile_position, distance))
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+        positions.sort_by_key(|tile_position| util::distance(position, *tile_position));
+        positions
+    }
 
-        successors
+    /// Successors where given animated corpse is allowed to go regarding tile occupancy
+    pub fn get_free_successors(
+        &self,
+        animated_corpse: &(dyn ac::AnimatedCorpse + Send + Sync),
+    ) -> Vec<((u32, u32), u32)> {
+        self.get_successors(animated_corpse.zone_row_i(), animated_corpse.zone_col_i())
+            .into_iter()
+            .filter(|(position, _)| {
+                self.occupancy
+                    .can_enter(animated_corpse.id(), animated_corpse.type_(), *position)
+            })
+            .collect()
     }
 }
 
+/// Grab zone animated corpses then create zone
+pub fn load(
+    world: &World,
+    client: &Client,
+    world_row_i: u32,
+    world_col_i: u32,
+    mode: ParseMode,
+) -> Result<Zone, error::Error> {
+    log::debug!("Zone {}.{}: grab animated corpses", world_row_i, world_col_i);
+    let animated_corpses = client.get_animated_corpses(world_row_i, world_col_i)?;
+    log::info!(
+        "Found {} animated corpses for zone {}.{}",
+        animated_corpses.len(),
+        world_row_i,
+        world_col_i
+    );
+    new(world, client, world_row_i, world_col_i, animated_corpses, mode)
+}
+
 pub fn new(
     world: &World,
     client: &Client,
     world_row_i: u32,
     world_col_i: u32,
     animated_corpses: Vec<Box<dyn AnimatedCorpse + Send + Sync>>,
+    mode: ParseMode,
 ) -> Result<Zone, error::Error> {
-    let world_tile_type_id = world.rows[world_row_i as usize].cols[world_col_i as usize].clone();
+    if !world.grid.contains((world_row_i, world_col_i)) {
+        return Err(error::Error::usage(format!(
+            "Zone {}.{} is outside world",
+            world_row_i, world_col_i
+        )));
+    }
     log::debug!("Zone {}.{}: grab tiles data", world_row_i, world_col_i);
     let server_tiles_data = client.get_tiles_data()?;
     let zone_tiles = ZoneTiles::new(server_tiles_data)?;
     log::debug!("Zone {}.{}: grab source", world_row_i, world_col_i);
-    let zone_raw = client.get_zone_source(world_row_i as u32, world_col_i as u32)?;
-    let zone_raw = util::extract_block_from_source(util::BLOCK_GEO, &zone_raw)?;
+    let zone_raw = client.get_zone_source(world_row_i, world_col_i)?;
+    let (document, source_warnings) = Document::parse(&zone_raw, mode)?;
     log::debug!("Zone {}.{}: grab characters", world_row_i, world_col_i);
-    let zone_characters = client.get_zone_characters(world_row_i as u32, world_col_i as u32)?;
+    let zone_characters = client.get_zone_characters(world_row_i, world_col_i)?;
     log::debug!("Zone {}.{}: grab builds", world_row_i, world_col_i);
-    let zone_builds = client.get_zone_builds(world_row_i as u32, world_col_i as u32)?;
+    let zone_builds = client.get_zone_builds(world_row_i, world_col_i)?;
 
-    Ok(Zone::new(
-        world_row_i as u32,
-        world_col_i as u32,
+    let (zone, zone_warnings) = Zone::new(
+        (world_row_i, world_col_i),
         animated_corpses,
         zone_characters,
         zone_builds,
-        &zone_raw,
+        document.require_geo()?,
         zone_tiles,
-        world_tile_type_id,
-        client.clone(),
-    )?)
+        mode,
+    )?;
+    for warning in source_warnings.iter().chain(zone_warnings.iter()) {
+        log::warn!(
+            "Zone {}.{} source: {}",
+            world_row_i,
+            world_col_i,
+            warning
+        );
+    }
+    Ok(zone)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::ac::{AnimatedCorpseBase, Emotion, Type};
+    use serde_json::json;
+
+    /// Animated corpse at given zone position, with default traits
+    pub fn animated_corpse(
+        id: u32,
+        type_: Type,
+        position: GridPosition,
+    ) -> Box<dyn AnimatedCorpse + Send + Sync> {
+        ac::animated_corpse_from_base(AnimatedCorpseBase {
+            id,
+            type_,
+            world_row_i: 0,
+            world_col_i: 0,
+            zone_row_i: position.0,
+            zone_col_i: position.1,
+            hunger: 0,
+            thirst: 0,
+            linger: 0,
+            asleep: false,
+            traits: None,
+            home: None,
+            heading: None,
+            emotion: Emotion::Calm,
+            emotion_cooldown: 0,
+        })
+    }
+
+    /// Zone of given GEO lines, with grass ('.', food), dirt (',') and water ('~') tiles
+    pub fn zone(lines: &str, animated_corpses: Vec<Box<dyn AnimatedCorpse + Send + Sync>>) -> Zone {
+        let tiles = ZoneTiles::new(json!([
+            {"id": "GRASS", "char": ".", "traversable": {"WALKING": true}, "food": true},
+            {"id": "DIRT", "char": ",", "traversable": {"WALKING": true}},
+            {"id": "WATER", "char": "~", "traversable": {"WALKING": false}, "water": true},
+        ]))
+        .unwrap();
+        let (document, _) =
+            Document::parse(&format!("::GEO\n{}", lines), ParseMode::Strict).unwrap();
+        let (zone, _) = Zone::new(
+            (0, 0),
+            animated_corpses,
+            vec![],
+            vec![],
+            document.require_geo().unwrap(),
+            tiles,
+            ParseMode::Strict,
+        )
+        .unwrap();
+        zone
+    }
+
+    /// Destinations of required moves
+    pub fn moves(messages: &[Message]) -> Vec<GridPosition> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::Event(SendEventMessage::RequireAnimatedCorpseMove(_, row_i, col_i), _) => {
+                    Some((*row_i, *col_i))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn leader_id(zone: &Zone, animated_corpse_id: AnimatedCorpseId) -> AnimatedCorpseId {
+        zone.herd_leaders[&animated_corpse_id]
+    }
+
+    fn bold(
+        mut animated_corpse: Box<dyn AnimatedCorpse + Send + Sync>,
+    ) -> Box<dyn AnimatedCorpse + Send + Sync> {
+        animated_corpse.base_mut().traits = Some(ac::traits::Traits {
+            boldness: 0.9,
+            ..Default::default()
+        });
+        animated_corpse
+    }
+
+    const LINES: &str = "....................\n....................\n";
+
+    #[test]
+    fn one_leader_per_herd() {
+        let zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (0, 0)),
+                bold(animated_corpse(2, Type::GOAT, (0, 6))),
+                animated_corpse(3, Type::GOAT, (1, 12)),
+                // Too far from other goats
+                animated_corpse(4, Type::GOAT, (0, 19)),
+                animated_corpse(5, Type::PIG, (0, 1)),
+            ],
+        );
+
+        assert_eq!(leader_id(&zone, 1), 2);
+        assert_eq!(leader_id(&zone, 3), 2);
+        assert_eq!(leader_id(&zone, 4), 4);
+        assert_eq!(leader_id(&zone, 5), 5);
+        let follower = zone.animated_corpse(1).unwrap();
+        assert_eq!(zone.herd_leader(follower).unwrap().id(), 2);
+        assert!(zone.herd_leader(zone.animated_corpse(2).unwrap()).is_none());
+    }
+
+    #[test]
+    fn leadership_passes_on_when_leader_leaves() {
+        let mut zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (0, 0)),
+                bold(animated_corpse(2, Type::GOAT, (0, 1))),
+                animated_corpse(3, Type::GOAT, (0, 2)),
+            ],
+        );
+        assert_eq!(leader_id(&zone, 1), 2);
+
+        zone.on_message(ZoneMessage::RemoveAnimatedCorpse(2));
+
+        assert!(zone.animated_corpse(2).is_none());
+        assert!(zone.occupancy.occupants((0, 1)).is_empty());
+        assert_eq!(leader_id(&zone, 3), 1);
+    }
+
+    #[test]
+    fn leader_keep_leadership_when_herds_merge() {
+        let mut zone = zone(
+            LINES,
+            vec![
+                animated_corpse(1, Type::GOAT, (0, 0)),
+                animated_corpse(2, Type::GOAT, (0, 19)),
+                bold(animated_corpse(3, Type::GOAT, (1, 19))),
+            ],
+        );
+        assert_eq!(leader_id(&zone, 1), 1);
+        assert_eq!(leader_id(&zone, 2), 3);
+
+        zone.on_message(ZoneMessage::UpdateAnimatedCorpsePosition(1, 0, 14));
+
+        assert_eq!(leader_id(&zone, 1), 3);
+        assert_eq!(zone.occupancy.occupants((0, 14)).len(), 1);
+    }
+
+    #[test]
+    fn add_animated_corpse_replace_same_one() {
+        let mut zone = zone(LINES, vec![animated_corpse(1, Type::GOAT, (0, 0))]);
+
+        zone.on_message(ZoneMessage::AddAnimatedCorpse(
+            *animated_corpse(1, Type::GOAT, (1, 1)).base(),
+        ));
+
+        assert_eq!(zone.animated_corpses.len(), 1);
+        assert_eq!(zone.animated_corpses[0].zone_row_i(), 1);
+    }
 }